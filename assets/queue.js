@@ -3,6 +3,8 @@ const socket = new WebSocket(WEBSOCKET_URL);
 let current_option = null;
 let available_options = [];
 let old_username = null;
+let last_refreshed = null;
+let rate_limited_until = null;
 
 socket.addEventListener("message", (event) => {
   {
@@ -12,6 +14,8 @@ socket.addEventListener("message", (event) => {
       case "UpdatePage":
         console.log("replacing main");
         document.getElementById("main").outerHTML = data["main_contents"];
+        document.getElementById("source-health").outerHTML =
+          data["source_health"];
         update_last_refreshed();
         break;
       case "SetUsername":
@@ -20,6 +24,12 @@ socket.addEventListener("message", (event) => {
       case "UsernameSuggestions":
         apply_username_suggestions(data["suggestions"]);
         break;
+      case "RateLimited":
+        rate_limited_until = new Date(data["next_refresh"]);
+        if (last_refreshed !== null) {
+          show_refresh_time();
+        }
+        break;
       default:
         console.log("unknown message:", data);
         break;
@@ -28,9 +38,22 @@ socket.addEventListener("message", (event) => {
 });
 
 function update_last_refreshed() {
-  const d = new Date();
-  const n = d.toLocaleTimeString();
-  document.getElementById("refresh-time").innerText = `last refresh: ${n}`;
+  last_refreshed = new Date();
+  show_refresh_time();
+}
+
+function show_refresh_time() {
+  let text = `last refresh: ${last_refreshed.toLocaleTimeString()}`;
+
+  if (rate_limited_until !== null && rate_limited_until > new Date()) {
+    const next = rate_limited_until.toLocaleTimeString([], {
+      hour: "2-digit",
+      minute: "2-digit",
+    });
+    text += `, rate limited, next refresh at ${next}`;
+  }
+
+  document.getElementById("refresh-time").innerText = text;
 }
 
 function set_username(name) {
@@ -1,8 +1,26 @@
+use std::collections::HashMap;
+
 use color_eyre::eyre::Context;
-use scraper::{ElementRef, Html, Selector};
+use jiff::Timestamp;
 use url::Url;
 
-use crate::model::RollupSetting;
+use crate::{
+    api::{client::GitHubClient, github::recent_comments, graphql::last_comments},
+    model::{BorsBackend, QueueState, Repo, RollupSetting},
+};
+
+mod homu;
+mod new_bors;
+
+/// Accounts bors posts its comments from
+pub const BORS_USERS: &[&str] = &["bors", "rust-bors[bot]"];
+
+/// The first markdown link target in a bors comment, which is the link to the build
+pub fn first_link(body: &str) -> Option<Url> {
+    let (_, rest) = body.split_once("](")?;
+    let (url, _) = rest.split_once(')')?;
+    Url::parse(url).ok()
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum BorsStatus {
@@ -27,110 +45,192 @@ pub struct BorsPr {
     pub title: String,
     pub position_in_queue: usize,
     pub running: bool,
+    /// Whether the status is that of a try build
+    pub try_build: bool,
     pub url: Url,
 }
 
+/// The queue of rust-lang/rust on homu, for tests
+#[cfg(test)]
+pub fn test_queue_url() -> Url {
+    Url::parse("https://bors.rust-lang.org/queue/rust").unwrap()
+}
+
+#[cfg(test)]
+impl BorsPr {
+    /// An approved PR at position `pr_number` in the queue, tests change whatever matters to them
+    pub fn test(pr_number: u64) -> Self {
+        Self {
+            pr_number,
+            approver: "reviewer".to_string(),
+            status: BorsStatus::Approved,
+            mergeable: true,
+            rollup_setting: RollupSetting::Unset,
+            priority: 0,
+            title: format!("PR #{pr_number}"),
+            position_in_queue: pr_number as usize,
+            running: false,
+            try_build: false,
+            url: test_queue_url(),
+        }
+    }
+}
+
+/// A row of the queue that we couldn't make sense of, or only partly
+#[derive(Debug, Clone)]
+pub struct RowProblem {
+    /// Position of the row in the queue, starting at 1
+    pub row: usize,
+    pub pr_number: Option<u64>,
+    pub reason: String,
+}
+
+/// Everything that went wrong while reading a queue, so scraper breakage is visible
+#[derive(Debug, Clone, Default)]
+pub struct ParseReport {
+    /// Columns we expected but the queue doesn't have (anymore)
+    pub missing_columns: Vec<String>,
+    pub skipped_rows: Vec<RowProblem>,
+    /// Rows that were read, but not everything in them looked right
+    pub warnings: Vec<RowProblem>,
+}
+
+impl ParseReport {
+    fn missing_column(&mut self, name: &str) {
+        tracing::error!("bors queue has no `{name}` column");
+        self.missing_columns.push(name.to_string());
+    }
+
+    fn skip(&mut self, row: usize, pr_number: Option<u64>, reason: String) {
+        tracing::error!("skipping bors queue row {row}: {reason}");
+        self.skipped_rows.push(RowProblem {
+            row,
+            pr_number,
+            reason,
+        });
+    }
+
+    fn warn(&mut self, row: usize, pr_number: Option<u64>, reason: String) {
+        tracing::warn!("bors queue row {row}: {reason}");
+        self.warnings.push(RowProblem {
+            row,
+            pr_number,
+            reason,
+        });
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct BorsQueue {
     pub items: Vec<BorsPr>,
+    pub state: QueueState,
+    pub parse_report: ParseReport,
 }
 
 impl BorsQueue {
+    /// An open queue of just `items`, for tests
+    #[cfg(test)]
+    pub fn test(items: Vec<BorsPr>) -> Self {
+        Self {
+            items,
+            ..Default::default()
+        }
+    }
+
     pub fn for_pr(&self, pr_number: u64) -> Option<&BorsPr> {
         self.items.iter().find(|i| i.pr_number == pr_number)
     }
 }
 
-pub async fn get_bors_info(url: Url) -> color_eyre::Result<BorsQueue> {
-    tracing::info!("requesting bors");
-
-    let mut prs = Vec::new();
-
-    let response = reqwest::get(url.clone()).await.context("get bors info")?;
-    let body = response.text().await.context("body")?;
+/// The last time bors tested a PR to merge it
+pub struct AutoBuild {
+    pub merge_commit: String,
+    /// The build bors linked to when it failed
+    pub failure_link: Option<Url>,
+}
 
+/// Finds the latest ":hourglass: Testing commit <head> with merge <merge>..." comment by bors
+pub async fn latest_auto_build(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_number: u64,
+) -> color_eyre::Result<Option<AutoBuild>> {
+    let comments = recent_comments(github, repo, pr_number)
+        .await
+        .context("get comments")?;
+
+    let mut res = None;
+
+    for body in comments
+        .iter()
+        .filter(|c| BORS_USERS.contains(&c.user.login.as_str()))
+        .filter_map(|c| c.body.as_deref())
     {
-        let document = Html::parse_document(&body);
-
-        let mut position_in_queue = 0;
-
-        let row_selector = Selector::parse("#queue tbody tr").unwrap();
-        for row in document.select(&row_selector) {
-            position_in_queue += 1;
-
-            let children = row
-                .children()
-                .filter_map(ElementRef::wrap)
-                .collect::<Vec<_>>();
-
-            let number = children[2].text().collect::<String>();
-            let status = children[3].text().collect::<String>();
-            let mergeable = children[4].text().collect::<String>();
-            let title = children[5].text().collect::<String>();
-            let approver = children[8].text().collect::<String>();
-            let priority = children[9].text().collect::<String>();
-            let rollup = children[10].text().collect::<String>();
-
-            let Ok(number) = number.trim().parse::<u64>() else {
-                tracing::error!("parse PR number");
-                continue;
-            };
-
-            let status = match status.trim() {
-                "" => BorsStatus::None,
-                "error" => BorsStatus::Error,
-                "failure" => BorsStatus::Failure,
-                "approved" => BorsStatus::Approved,
-                "pending" => BorsStatus::Pending,
-                "success" => BorsStatus::Success,
-                other => BorsStatus::Other(other.to_string()),
-            };
-
-            let mergeable = match mergeable.trim() {
-                "" => {
-                    tracing::warn!("mergable empty");
-                    true
-                }
-                "yes" => true,
-                "no" => false,
-                other => {
-                    tracing::error!("weird mergeable status: {other}");
-                    continue;
-                }
-            };
-
-            let rollup_status = match rollup.trim() {
-                "" => RollupSetting::Unset,
-                "never" => RollupSetting::Never,
-                "always" => RollupSetting::Always,
-                "iffy" => RollupSetting::Iffy,
-                other => {
-                    tracing::error!("weird rollup status: {other}");
-                    continue;
-                }
-            };
-
-            let Ok(priority) = priority.trim().parse::<u64>() else {
-                tracing::error!("parse priority: {}", priority.trim());
-                continue;
-            };
-
-            let res = BorsPr {
-                pr_number: number,
-                approver: approver.trim().to_string(),
-                status,
-                mergeable,
-                rollup_setting: rollup_status,
-                priority,
-                title: title.trim().to_string(),
-                position_in_queue: position_in_queue,
-                running: position_in_queue == 1,
-                url: url.clone(),
-            };
-
-            prs.push(res);
+        if let Some((_, rest)) = body.split_once("Testing commit ")
+            && let Some((_head, rest)) = rest.split_once(" with merge ")
+        {
+            let merge_commit = rest
+                .chars()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .collect::<String>();
+
+            res = Some(AutoBuild {
+                merge_commit,
+                failure_link: None,
+            });
+        } else if let Some(build) = &mut res
+            && body.contains("Test failed")
+        {
+            build.failure_link = first_link(body);
         }
     }
 
-    Ok(BorsQueue { items: prs })
+    Ok(res.filter(|i| !i.merge_commit.is_empty()))
+}
+
+/// homu says ":broken_heart: Test failed" or ":boom: Test timed out", the new bors
+/// ":broken_heart: Test for <sha> failed"
+fn is_failure_comment(body: &str) -> bool {
+    body.starts_with(":broken_heart:")
+        || body.contains("Test failed")
+        || body.contains("Test timed out")
+}
+
+/// When bors last said it failed to merge each of a bunch of PRs
+pub async fn failed_at(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_numbers: &[u64],
+) -> color_eyre::Result<HashMap<u64, Timestamp>> {
+    let comments = last_comments(github, repo, pr_numbers)
+        .await
+        .context("get comments")?;
+
+    Ok(comments
+        .into_iter()
+        .filter_map(|(number, comments)| {
+            let failure = comments
+                .iter()
+                .rev()
+                .filter(|c| {
+                    c.author().is_some_and(|author| {
+                        BORS_USERS
+                            .iter()
+                            .any(|i| i.trim_end_matches("[bot]") == author)
+                    })
+                })
+                .find(|c| is_failure_comment(&c.body))?;
+
+            Some((number, failure.created_at))
+        })
+        .collect())
+}
+
+pub async fn get_bors_info(backend: BorsBackend) -> color_eyre::Result<BorsQueue> {
+    match backend {
+        BorsBackend::Homu { queue_url } => homu::get_bors_info(queue_url).await,
+        BorsBackend::NewBors { api_url, repo_name } => {
+            new_bors::get_bors_info(api_url, repo_name).await
+        }
+    }
 }
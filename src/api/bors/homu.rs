@@ -0,0 +1,384 @@
+use color_eyre::eyre::{Context, eyre};
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+use crate::{
+    api::bors::{BorsPr, BorsQueue, BorsStatus, ParseReport},
+    model::{QueueState, RollupSetting},
+};
+
+/// Where each column we care about is in the queue table, found by header text
+struct Columns {
+    number: usize,
+    status: Option<usize>,
+    mergeable: Option<usize>,
+    title: Option<usize>,
+    approver: Option<usize>,
+    priority: Option<usize>,
+    rollup: Option<usize>,
+}
+
+impl Columns {
+    /// None without a PR number column, the missing columns are in the report either way
+    fn from_headers(headers: &[String], report: &mut ParseReport) -> Option<Self> {
+        let mut find = |name: &str| {
+            let res = headers.iter().position(|i| i == name);
+            if res.is_none() {
+                report.missing_column(name);
+            }
+            res
+        };
+
+        // every column is looked for before giving up, so the report has all that's missing
+        let number = find("#");
+        let status = find("status");
+        let mergeable = find("mergeable");
+        let title = find("title");
+        let approver = find("approved by");
+        let priority = find("priority");
+        let rollup = find("rollup");
+
+        Some(Self {
+            number: number?,
+            status,
+            mergeable,
+            title,
+            approver,
+            priority,
+            rollup,
+        })
+    }
+}
+
+/// homu says "Tree closed for PRs with priority less than 100 (see this comment)." above the queue
+fn parse_queue_state(document: &Html) -> QueueState {
+    let paragraph_selector = Selector::parse("p").unwrap();
+    let link_selector = Selector::parse("a").unwrap();
+
+    for paragraph in document.select(&paragraph_selector) {
+        let text = paragraph.text().collect::<String>();
+        let Some((_, rest)) = text.split_once("priority less than") else {
+            continue;
+        };
+
+        let priority = rest
+            .trim_start()
+            .split(|c: char| !c.is_ascii_digit())
+            .next();
+        let Some(priority) = priority.and_then(|i| i.parse::<u64>().ok()) else {
+            tracing::error!("parse tree closed priority: {}", text.trim());
+            continue;
+        };
+
+        return QueueState::TreeClosed {
+            priority,
+            reason: None,
+            source: paragraph
+                .select(&link_selector)
+                .filter_map(|i| i.attr("href"))
+                .find_map(|i| Url::parse(i).ok()),
+        };
+    }
+
+    QueueState::Open
+}
+
+/// Scrapes the queue page of homu, the original bors
+pub async fn get_bors_info(url: Url) -> color_eyre::Result<BorsQueue> {
+    tracing::info!("requesting bors");
+
+    let response = reqwest::get(url.clone()).await.context("get bors info")?;
+    let body = response.text().await.context("body")?;
+
+    parse_queue(&body, &url)
+}
+
+/// `url` is the queue page itself, which is where the PRs in it link to
+fn parse_queue(body: &str, url: &Url) -> color_eyre::Result<BorsQueue> {
+    let mut prs = Vec::new();
+    let mut report = ParseReport::default();
+    let state;
+
+    {
+        let document = Html::parse_document(body);
+        state = parse_queue_state(&document);
+
+        let header_selector = Selector::parse("#queue thead th").unwrap();
+        let headers = document
+            .select(&header_selector)
+            .map(|i| i.text().collect::<String>().trim().to_lowercase())
+            .collect::<Vec<_>>();
+        // without PR numbers none of the rows mean anything
+        let Some(columns) = Columns::from_headers(&headers, &mut report) else {
+            return Err(eyre!(
+                "bors queue has no PR number column, missing columns: {}",
+                report.missing_columns.join(", ")
+            ));
+        };
+
+        let mut position_in_queue = 0;
+
+        let row_selector = Selector::parse("#queue tbody tr").unwrap();
+        for row in document.select(&row_selector) {
+            position_in_queue += 1;
+
+            let children = row
+                .children()
+                .filter_map(ElementRef::wrap)
+                .collect::<Vec<_>>();
+
+            // a missing column reads as an empty cell, which we treat as the default
+            let cell = |column: Option<usize>| {
+                column
+                    .and_then(|i| children.get(i))
+                    .map(|i| i.text().collect::<String>().trim().to_string())
+                    .unwrap_or_default()
+            };
+
+            let number = cell(Some(columns.number));
+            let Ok(number) = number.parse::<u64>() else {
+                report.skip(
+                    position_in_queue,
+                    None,
+                    format!("weird PR number: '{number}'"),
+                );
+                continue;
+            };
+
+            // missing cells read as empty, so the row is still worth showing
+            if children.len() != headers.len() {
+                report.warn(
+                    position_in_queue,
+                    Some(number),
+                    format!(
+                        "row has {} cells but there are {} headers",
+                        children.len(),
+                        headers.len()
+                    ),
+                );
+            }
+
+            let status = cell(columns.status);
+            // try builds show up as for example "pending (try)"
+            let (status, try_build) = match status.strip_suffix("(try)") {
+                Some(status) => (status.trim(), true),
+                None => (status.as_str(), false),
+            };
+
+            let status = match status {
+                "" => BorsStatus::None,
+                "error" => BorsStatus::Error,
+                "failure" => BorsStatus::Failure,
+                "approved" => BorsStatus::Approved,
+                "pending" => BorsStatus::Pending,
+                "success" => BorsStatus::Success,
+                other => BorsStatus::Other(other.to_string()),
+            };
+
+            let mergeable = match cell(columns.mergeable).as_str() {
+                "" => {
+                    tracing::warn!("mergable empty");
+                    true
+                }
+                "yes" => true,
+                "no" => false,
+                other => {
+                    report.skip(
+                        position_in_queue,
+                        Some(number),
+                        format!("weird mergeable status: '{other}'"),
+                    );
+                    continue;
+                }
+            };
+
+            let rollup_status = match cell(columns.rollup).as_str() {
+                "" => RollupSetting::Unset,
+                "never" => RollupSetting::Never,
+                "always" => RollupSetting::Always,
+                "iffy" => RollupSetting::Iffy,
+                other => {
+                    report.skip(
+                        position_in_queue,
+                        Some(number),
+                        format!("weird rollup status: '{other}'"),
+                    );
+                    continue;
+                }
+            };
+
+            let priority = match cell(columns.priority).as_str() {
+                "" => 0,
+                other => match other.parse::<u64>() {
+                    Ok(i) => i,
+                    Err(_) => {
+                        report.skip(
+                            position_in_queue,
+                            Some(number),
+                            format!("weird priority: '{other}'"),
+                        );
+                        continue;
+                    }
+                },
+            };
+
+            let res = BorsPr {
+                pr_number: number,
+                approver: cell(columns.approver),
+                status,
+                mergeable,
+                rollup_setting: rollup_status,
+                priority,
+                title: cell(columns.title),
+                position_in_queue,
+                running: position_in_queue == 1 && !try_build,
+                try_build,
+                url: url.clone(),
+            };
+
+            prs.push(res);
+        }
+    }
+
+    Ok(BorsQueue {
+        items: prs,
+        state,
+        parse_report: report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Columns, parse_queue};
+    use crate::{
+        api::bors::{BorsStatus, ParseReport, test_queue_url},
+        model::{QueueState, RollupSetting},
+    };
+
+    const QUEUE: &str = include_str!("fixtures/homu_queue.html");
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn columns_are_found_by_header() {
+        let mut report = ParseReport::default();
+        let columns = Columns::from_headers(
+            &headers(&[
+                "rollup",
+                "#",
+                "title",
+                "status",
+                "mergeable",
+                "approved by",
+                "priority",
+            ]),
+            &mut report,
+        )
+        .unwrap();
+
+        assert_eq!(columns.number, 1);
+        assert_eq!(columns.rollup, Some(0));
+        assert_eq!(columns.priority, Some(6));
+        assert!(report.missing_columns.is_empty());
+        assert!(report.skipped_rows.is_empty());
+    }
+
+    #[test]
+    fn missing_columns_are_reported() {
+        let mut report = ParseReport::default();
+        let columns = Columns::from_headers(&headers(&["#", "status", "title"]), &mut report);
+
+        assert!(columns.is_some());
+        assert_eq!(
+            report.missing_columns,
+            ["mergeable", "approved by", "priority", "rollup"]
+        );
+    }
+
+    #[test]
+    fn missing_number_column_keeps_the_report() {
+        let mut report = ParseReport::default();
+        let columns = Columns::from_headers(&headers(&["status", "title"]), &mut report);
+
+        assert!(columns.is_none());
+        assert_eq!(report.missing_columns[0], "#");
+
+        let body = QUEUE.replace("<th>#</th>", "<th>number</th>");
+        let error = parse_queue(&body, &test_queue_url())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("missing columns: #"), "{error}");
+    }
+
+    #[test]
+    fn rows_are_parsed() {
+        let queue = parse_queue(QUEUE, &test_queue_url()).unwrap();
+        let numbers = queue.items.iter().map(|i| i.pr_number).collect::<Vec<_>>();
+        assert_eq!(numbers, [140001, 140002, 140003, 140004, 140006]);
+
+        let rollup = &queue.items[0];
+        assert_eq!(rollup.status, BorsStatus::Pending);
+        assert!(rollup.running);
+        assert!(matches!(rollup.rollup_setting, RollupSetting::Never));
+        assert_eq!(rollup.priority, 1);
+
+        let approved = &queue.items[1];
+        assert_eq!(approved.status, BorsStatus::Approved);
+        assert_eq!(approved.approver, "reviewer,other");
+        assert_eq!(approved.title, "Fix ICE in borrowck");
+        assert_eq!(approved.position_in_queue, 2);
+        assert!(!approved.running);
+        assert!(matches!(approved.rollup_setting, RollupSetting::Iffy));
+
+        let unmergeable = &queue.items[2];
+        assert!(!unmergeable.mergeable);
+        assert_eq!(unmergeable.priority, 0);
+
+        let try_build = &queue.items[3];
+        assert!(try_build.try_build);
+        assert_eq!(try_build.status, BorsStatus::Pending);
+        assert!(matches!(try_build.rollup_setting, RollupSetting::Unset));
+    }
+
+    #[test]
+    fn weird_rows_are_skipped() {
+        let queue = parse_queue(QUEUE, &test_queue_url()).unwrap();
+        let skipped = &queue.parse_report.skipped_rows;
+
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].row, 5);
+        assert_eq!(skipped[0].pr_number, Some(140005));
+        assert!(skipped[0].reason.contains("rollup"));
+    }
+
+    #[test]
+    fn rows_missing_cells_are_kept_with_a_warning() {
+        let queue = parse_queue(QUEUE, &test_queue_url()).unwrap();
+        let warnings = &queue.parse_report.warnings;
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].row, 6);
+        assert_eq!(warnings[0].pr_number, Some(140006));
+        assert!(warnings[0].reason.contains("cells"));
+
+        let short = queue.for_pr(140006).unwrap();
+        assert_eq!(short.title, "Row with a cell missing");
+        assert_eq!(short.status, BorsStatus::None);
+    }
+
+    #[test]
+    fn tree_closed_is_parsed() {
+        let queue = parse_queue(QUEUE, &test_queue_url()).unwrap();
+
+        let QueueState::TreeClosed {
+            priority, source, ..
+        } = queue.state
+        else {
+            panic!("tree should be closed");
+        };
+        assert_eq!(priority, 100);
+        assert!(source.is_some());
+    }
+}
@@ -0,0 +1,267 @@
+use color_eyre::eyre::Context;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    api::bors::{BorsPr, BorsQueue, BorsStatus, ParseReport},
+    model::{QueueState, RollupSetting},
+};
+
+#[derive(Deserialize)]
+struct Build {
+    status: String,
+}
+
+#[derive(Deserialize)]
+struct PullRequest {
+    number: u64,
+    title: String,
+    #[serde(default)]
+    approver: Option<String>,
+    #[serde(default)]
+    priority: Option<u64>,
+    #[serde(default)]
+    rollup: Option<String>,
+    #[serde(default)]
+    mergeable_state: Option<String>,
+    /// The build that merges the PR once it passes
+    #[serde(default)]
+    auto_build: Option<Build>,
+    #[serde(default)]
+    try_build: Option<Build>,
+}
+
+/// `{api_url}/api/repository/{repo_name}`
+#[derive(Deserialize)]
+struct Repository {
+    /// Set while the tree is closed for PRs below this priority
+    #[serde(default)]
+    tree_closed_priority: Option<u64>,
+    #[serde(default)]
+    tree_closed_reason: Option<String>,
+    #[serde(default)]
+    tree_closed_source: Option<Url>,
+    #[serde(default)]
+    paused: bool,
+    #[serde(default)]
+    paused_reason: Option<String>,
+}
+
+async fn get_queue_state(api_url: &Url, repo_name: &str) -> color_eyre::Result<QueueState> {
+    let url = api_url
+        .join(&format!("api/repository/{repo_name}"))
+        .context("repository url")?;
+    let response = reqwest::get(url).await.context("get repository")?;
+    let body = response.text().await.context("body")?;
+
+    parse_queue_state(&body)
+}
+
+/// Being paused wins over the tree being closed, nothing gets merged either way
+fn parse_queue_state(body: &str) -> color_eyre::Result<QueueState> {
+    let repository: Repository = serde_json::from_str(body).context("repository")?;
+
+    Ok(if repository.paused {
+        QueueState::Paused {
+            reason: repository.paused_reason,
+        }
+    } else if let Some(priority) = repository.tree_closed_priority {
+        QueueState::TreeClosed {
+            priority,
+            reason: repository.tree_closed_reason,
+            source: repository.tree_closed_source,
+        }
+    } else {
+        QueueState::Open
+    })
+}
+
+fn build_status(build: &Build) -> BorsStatus {
+    match build.status.as_str() {
+        "pending" => BorsStatus::Pending,
+        "success" => BorsStatus::Success,
+        "failure" => BorsStatus::Failure,
+        "cancelled" | "timeouted" => BorsStatus::Error,
+        other => BorsStatus::Other(other.to_string()),
+    }
+}
+
+/// Gets the queue of rust-lang/bors from `{api_url}/api/queue/{repo_name}`
+pub async fn get_bors_info(api_url: Url, repo_name: String) -> color_eyre::Result<BorsQueue> {
+    tracing::info!("requesting new bors");
+
+    let url = api_url
+        .join(&format!("api/queue/{repo_name}"))
+        .context("queue url")?;
+    let response = reqwest::get(url).await.context("get bors info")?;
+    let body = response.text().await.context("body")?;
+
+    // the queue itself is still useful when we don't know whether the tree is open
+    let state = get_queue_state(&api_url, &repo_name)
+        .await
+        .unwrap_or_else(|e| {
+            tracing::error!("bors queue state error: {e}");
+            QueueState::Open
+        });
+
+    // link people to the human readable queue page
+    let queue_url = api_url
+        .join(&format!("queue/{repo_name}"))
+        .context("queue url")?;
+
+    parse_queue(&body, &queue_url, state)
+}
+
+/// `queue_url` is the human readable queue page, which is where the PRs in it link to
+fn parse_queue(body: &str, queue_url: &Url, state: QueueState) -> color_eyre::Result<BorsQueue> {
+    let rows: Vec<serde_json::Value> = serde_json::from_str(body).context("queue")?;
+
+    // decode rows one by one, so a single weird PR doesn't hide the whole queue
+    let mut report = ParseReport::default();
+    let mut prs = Vec::new();
+    for (idx, row) in rows.into_iter().enumerate() {
+        let pr_number = row.get("number").and_then(|i| i.as_u64());
+        match serde_json::from_value::<PullRequest>(row) {
+            Ok(pr) => prs.push(pr),
+            Err(e) => report.skip(idx + 1, pr_number, e.to_string()),
+        }
+    }
+
+    // the order homu uses: running first, then approved PRs by priority, then the rest
+    prs.sort_by_key(|pr| {
+        (
+            pr.auto_build.as_ref().is_none_or(|b| b.status != "pending"),
+            pr.approver.is_none(),
+            std::cmp::Reverse(pr.priority.unwrap_or(0)),
+            pr.number,
+        )
+    });
+
+    let items = prs
+        .into_iter()
+        .enumerate()
+        .map(|(idx, pr)| {
+            let (status, try_build) = match (&pr.auto_build, &pr.try_build) {
+                (Some(build), _) => (build_status(build), false),
+                (None, Some(build)) => (build_status(build), true),
+                (None, None) if pr.approver.is_some() => (BorsStatus::Approved, false),
+                (None, None) => (BorsStatus::None, false),
+            };
+
+            let position_in_queue = idx + 1;
+
+            BorsPr {
+                pr_number: pr.number,
+                approver: pr.approver.unwrap_or_default(),
+                running: matches!(status, BorsStatus::Pending) && !try_build,
+                status,
+                mergeable: pr.mergeable_state.as_deref() != Some("has_conflicts"),
+                rollup_setting: match pr.rollup.as_deref() {
+                    Some("never") => RollupSetting::Never,
+                    Some("always") => RollupSetting::Always,
+                    Some("iffy") => RollupSetting::Iffy,
+                    _ => RollupSetting::Unset,
+                },
+                priority: pr.priority.unwrap_or(0),
+                title: pr.title,
+                position_in_queue,
+                try_build,
+                url: queue_url.clone(),
+            }
+        })
+        .collect();
+
+    Ok(BorsQueue {
+        items,
+        state,
+        parse_report: report,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_queue, parse_queue_state};
+    use crate::{
+        api::bors::{BorsStatus, test_queue_url},
+        model::{QueueState, RollupSetting},
+    };
+
+    const QUEUE: &str = include_str!("fixtures/new_bors_queue.json");
+    const CLOSED: &str = include_str!("fixtures/new_bors_repository_closed.json");
+    const PAUSED: &str = include_str!("fixtures/new_bors_repository_paused.json");
+
+    #[test]
+    fn prs_are_parsed_in_queue_order() {
+        let queue = parse_queue(QUEUE, &test_queue_url(), QueueState::Open).unwrap();
+        let numbers = queue.items.iter().map(|i| i.pr_number).collect::<Vec<_>>();
+        assert_eq!(numbers, [140010, 140011, 140012, 140015, 140013]);
+
+        let rollup = &queue.items[0];
+        assert_eq!(rollup.status, BorsStatus::Pending);
+        assert!(rollup.running);
+        assert!(matches!(rollup.rollup_setting, RollupSetting::Never));
+        assert_eq!(rollup.url, test_queue_url());
+
+        let approved = &queue.items[1];
+        assert_eq!(approved.status, BorsStatus::Approved);
+        assert_eq!(approved.approver, "reviewer");
+        assert_eq!(approved.priority, 5);
+        assert_eq!(approved.position_in_queue, 2);
+        assert!(matches!(approved.rollup_setting, RollupSetting::Iffy));
+
+        let conflicted = &queue.items[2];
+        assert!(!conflicted.mergeable);
+        assert!(matches!(conflicted.rollup_setting, RollupSetting::Always));
+
+        let failed = &queue.items[3];
+        assert_eq!(failed.status, BorsStatus::Failure);
+        assert!(!failed.running);
+
+        let try_build = &queue.items[4];
+        assert!(try_build.try_build);
+        assert_eq!(try_build.status, BorsStatus::Pending);
+        assert!(!try_build.running);
+        assert!(matches!(try_build.rollup_setting, RollupSetting::Unset));
+    }
+
+    #[test]
+    fn weird_prs_are_skipped() {
+        let queue = parse_queue(QUEUE, &test_queue_url(), QueueState::Open).unwrap();
+        let skipped = &queue.parse_report.skipped_rows;
+
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].row, 5);
+        assert_eq!(skipped[0].pr_number, Some(140014));
+    }
+
+    #[test]
+    fn tree_closed_is_parsed() {
+        let QueueState::TreeClosed {
+            priority,
+            reason,
+            source,
+        } = parse_queue_state(CLOSED).unwrap()
+        else {
+            panic!("tree should be closed");
+        };
+
+        assert_eq!(priority, 100);
+        assert_eq!(reason.as_deref(), Some("CI is broken"));
+        assert!(source.is_some());
+    }
+
+    #[test]
+    fn paused_wins_over_tree_closed() {
+        let QueueState::Paused { reason } = parse_queue_state(PAUSED).unwrap() else {
+            panic!("queue should be paused");
+        };
+
+        assert_eq!(reason.as_deref(), Some("release week"));
+    }
+
+    #[test]
+    fn open_without_closed_priority() {
+        let body = r#"{"name": "rust-lang/rust", "paused": false}"#;
+        assert!(matches!(parse_queue_state(body).unwrap(), QueueState::Open));
+    }
+}
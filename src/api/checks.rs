@@ -0,0 +1,260 @@
+use color_eyre::eyre::Context;
+use http_body_util::BodyExt;
+use jiff::Timestamp;
+use octocrab::map_github_error;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    api::{PrCache, client::GitHubClient, rate_limit::RateLimited},
+    model::{Check, CheckConclusion, Repo},
+};
+
+#[derive(Deserialize)]
+pub struct CheckRuns {
+    pub check_runs: Vec<CheckRun>,
+}
+
+#[derive(Deserialize)]
+pub struct CheckRun {
+    /// For GitHub Actions, also the id of the job
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub html_url: Option<Url>,
+    pub started_at: Option<Timestamp>,
+    pub completed_at: Option<Timestamp>,
+}
+
+impl CheckRun {
+    pub fn failed(&self) -> bool {
+        matches!(
+            self.conclusion.as_deref(),
+            Some("failure" | "timed_out" | "cancelled")
+        )
+    }
+}
+
+pub async fn check_runs(
+    github: &GitHubClient,
+    repo: &Repo,
+    sha: &str,
+) -> color_eyre::Result<CheckRuns> {
+    let route = format!(
+        "/repos/{}/{}/commits/{sha}/check-runs",
+        repo.owner, repo.name
+    );
+
+    github
+        .request(async |octocrab| {
+            octocrab
+                .get(route, Some(&[("per_page", 100)]))
+                .await
+                .context("get check runs")
+        })
+        .await
+}
+
+/// Statuses are the older API that some CI systems still report through
+#[derive(Deserialize)]
+struct CombinedStatus {
+    statuses: Vec<CommitStatus>,
+}
+
+#[derive(Deserialize)]
+pub struct CommitStatus {
+    pub state: String,
+    pub context: String,
+    /// Can be an empty string
+    pub target_url: Option<String>,
+    pub created_at: Timestamp,
+    /// Not available through GraphQL
+    #[serde(default)]
+    pub updated_at: Option<Timestamp>,
+}
+
+async fn commit_statuses(
+    github: &GitHubClient,
+    repo: &Repo,
+    sha: &str,
+) -> color_eyre::Result<Vec<CommitStatus>> {
+    let route = format!("/repos/{}/{}/commits/{sha}/status", repo.owner, repo.name);
+
+    let combined: CombinedStatus = github
+        .request(async |octocrab| {
+            octocrab
+                .get(route, Some(&[("per_page", 100)]))
+                .await
+                .context("get commit statuses")
+        })
+        .await?;
+
+    Ok(combined.statuses)
+}
+
+impl From<CheckRun> for Check {
+    fn from(run: CheckRun) -> Self {
+        let conclusion = match (run.status.as_str(), run.conclusion.as_deref()) {
+            ("completed", Some("success")) => CheckConclusion::Success,
+            ("completed", _) if run.failed() => CheckConclusion::Failure,
+            ("completed", Some("neutral" | "skipped" | "stale")) => CheckConclusion::Skipped,
+            // queued, in progress or waiting for someone to approve it
+            _ => CheckConclusion::Pending,
+        };
+
+        let end = run.completed_at.unwrap_or_else(Timestamp::now);
+
+        Check {
+            name: run.name,
+            conclusion,
+            duration: run.started_at.map(|start| end.duration_since(start)),
+            url: run.html_url,
+        }
+    }
+}
+
+impl From<CommitStatus> for Check {
+    fn from(status: CommitStatus) -> Self {
+        let (conclusion, end) = match status.state.as_str() {
+            "success" => (CheckConclusion::Success, status.updated_at),
+            "failure" | "error" => (CheckConclusion::Failure, status.updated_at),
+            _ => (CheckConclusion::Pending, Some(Timestamp::now())),
+        };
+
+        Check {
+            name: status.context,
+            conclusion,
+            duration: end.map(|end| end.duration_since(status.created_at)),
+            url: status.target_url.and_then(|i| Url::parse(&i).ok()),
+        }
+    }
+}
+
+/// Every check run and commit status on a commit
+pub async fn get_checks(
+    github: &GitHubClient,
+    repo: &Repo,
+    sha: &str,
+) -> color_eyre::Result<Vec<Check>> {
+    let runs = check_runs(github, repo, sha).await?.check_runs;
+    let statuses = commit_statuses(github, repo, sha).await?;
+
+    Ok(runs
+        .into_iter()
+        .map(Check::from)
+        .chain(statuses.into_iter().map(Check::from))
+        .collect())
+}
+
+pub async fn failed_jobs(
+    github: &GitHubClient,
+    repo: &Repo,
+    sha: &str,
+) -> color_eyre::Result<Vec<CheckRun>> {
+    Ok(check_runs(github, repo, sha)
+        .await?
+        .check_runs
+        .into_iter()
+        .filter(|i| i.failed())
+        .collect())
+}
+
+/// Failures are at the end of a log, and logs can be hundreds of megabytes, so only this much of
+/// the end is kept
+const MAX_LOG_BYTES: usize = 2 * 1024 * 1024;
+
+/// Drops everything but the last `max` bytes, starting at a line boundary
+fn keep_tail(log: &mut Vec<u8>, max: usize) {
+    if log.len() <= max {
+        return;
+    }
+
+    let cut = log.len() - max;
+    let cut = log[cut..]
+        .iter()
+        .position(|&i| i == b'\n')
+        .map_or(cut, |i| cut + i + 1);
+    log.drain(..cut);
+}
+
+/// The end of the raw log of a GitHub Actions job, streamed so at most about twice
+/// [`MAX_LOG_BYTES`] is held at a time. GitHub redirects to the log file, which octocrab follows.
+pub async fn job_log(
+    github: &GitHubClient,
+    repo: &Repo,
+    job_id: u64,
+) -> color_eyre::Result<String> {
+    let route = format!(
+        "/repos/{}/{}/actions/jobs/{job_id}/logs",
+        repo.owner, repo.name
+    );
+
+    github
+        .request(async |octocrab| {
+            let response = octocrab._get(route).await.context("get job log")?;
+            // raw responses aren't turned into errors by octocrab itself
+            if let Some(rate_limited) =
+                RateLimited::from_response(response.status(), response.headers())
+            {
+                return Err(rate_limited.into());
+            }
+            let mut body = map_github_error(response)
+                .await
+                .context("get job log")?
+                .into_body();
+
+            let mut log = Vec::new();
+            while let Some(frame) = body.frame().await {
+                if let Ok(data) = frame.context("log body")?.into_data() {
+                    log.extend_from_slice(&data);
+                }
+                if log.len() > 2 * MAX_LOG_BYTES {
+                    keep_tail(&mut log, MAX_LOG_BYTES);
+                }
+            }
+            keep_tail(&mut log, MAX_LOG_BYTES);
+
+            Ok(String::from_utf8_lossy(&log).into_owned())
+        })
+        .await
+}
+
+/// The checks on the head commit of a PR, cached until it's pushed to. Checks that are still
+/// running are only used once, after that they're fetched again.
+pub async fn head_checks(
+    github: &GitHubClient,
+    cache: &PrCache<Vec<Check>, String>,
+    repo: &Repo,
+    number: u64,
+    sha: &str,
+) -> color_eyre::Result<Vec<Check>> {
+    let checks = cache
+        .get_or_reload(repo, number, sha.to_string(), get_checks(github, repo, sha))
+        .await?;
+
+    if checks
+        .iter()
+        .any(|i| i.conclusion == CheckConclusion::Pending)
+    {
+        cache.remove(repo, number).await;
+    }
+
+    Ok(checks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::keep_tail;
+
+    #[test]
+    fn tail_starts_at_a_line() {
+        let mut log = b"first line\nsecond line\nthird\n".to_vec();
+        keep_tail(&mut log, 15);
+        assert_eq!(log, b"third\n");
+
+        let mut short = b"short\n".to_vec();
+        keep_tail(&mut short, 15);
+        assert_eq!(short, b"short\n");
+    }
+}
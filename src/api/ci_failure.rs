@@ -0,0 +1,172 @@
+use jiff::Timestamp;
+use url::Url;
+
+use crate::{
+    api::{
+        bors::latest_auto_build,
+        checks::{failed_jobs, job_log},
+        client::GitHubClient,
+        spurious::SpuriousFailures,
+    },
+    model::{Check, CheckConclusion, CiFailure, GitHubPr, Repo},
+};
+
+/// Lines of context in an excerpt
+const EXCERPT_LINES: usize = 20;
+
+/// Every line of a GitHub Actions log starts with a timestamp
+fn strip_timestamp(line: &str) -> &str {
+    match line.split_once(' ') {
+        Some((timestamp, rest)) if timestamp.parse::<Timestamp>().is_ok() => rest,
+        _ => line,
+    }
+}
+
+/// The first compiler error, up to the blank line that ends it
+fn compiler_error(lines: &[&str]) -> Option<String> {
+    let start = lines
+        .iter()
+        .position(|i| i.starts_with("error[") || i.starts_with("error:"))?;
+
+    let block = lines[start..]
+        .iter()
+        .take(EXCERPT_LINES)
+        .take_while(|i| !i.trim().is_empty())
+        .copied()
+        .collect::<Vec<_>>();
+
+    Some(block.join("\n"))
+}
+
+/// libtest lists the names of failing tests indented under the last `failures:`
+fn failing_tests(lines: &[&str]) -> Option<String> {
+    let start = lines.iter().rposition(|i| i.trim() == "failures:")?;
+
+    let tests = lines[start + 1..]
+        .iter()
+        .skip_while(|i| i.trim().is_empty())
+        .take_while(|i| i.starts_with("    "))
+        .map(|i| i.trim())
+        .take(EXCERPT_LINES)
+        .collect::<Vec<_>>();
+
+    (!tests.is_empty()).then(|| format!("failing tests:\n{}", tests.join("\n")))
+}
+
+/// Whatever happened right before things were declared `FAILED`
+fn around_failed(lines: &[&str]) -> Option<String> {
+    let idx = lines.iter().position(|i| i.contains("FAILED"))?;
+    let start = idx.saturating_sub(EXCERPT_LINES - 5);
+    let end = (idx + 5).min(lines.len());
+
+    Some(lines[start..end].join("\n"))
+}
+
+/// The part of a CI log that says what went wrong
+fn log_excerpt(log: &str) -> Option<String> {
+    let lines = log.lines().map(strip_timestamp).collect::<Vec<_>>();
+
+    compiler_error(&lines)
+        .or_else(|| failing_tests(&lines))
+        .or_else(|| around_failed(&lines))
+}
+
+/// What a CI failure was found for. A rerun on the same head shows up as other failed checks, a
+/// new attempt by bors as the PR being updated by its comment.
+#[derive(Clone, PartialEq)]
+pub struct CiFailureVersion {
+    updated_at: Timestamp,
+    head_sha: String,
+    /// With their URLs, which change when they're rerun
+    failed_checks: Vec<(String, Option<Url>)>,
+}
+
+impl CiFailureVersion {
+    pub fn new(pr: &GitHubPr, checks: &[Check]) -> Self {
+        Self {
+            updated_at: pr.issue.updated_at,
+            head_sha: pr.head_sha.clone(),
+            failed_checks: checks
+                .iter()
+                .filter(|i| i.conclusion == CheckConclusion::Failure)
+                .map(|i| (i.name.clone(), i.url.clone()))
+                .collect(),
+        }
+    }
+}
+
+/// The first failing job of a PR, either on its head commit or in the last time bors tested it
+pub async fn get_ci_failure(
+    github: &GitHubClient,
+    spurious_failures: &SpuriousFailures,
+    repo: &Repo,
+    pr_number: u64,
+    head_sha: &str,
+) -> color_eyre::Result<Option<CiFailure>> {
+    let mut jobs = failed_jobs(github, repo, head_sha).await?;
+
+    if jobs.is_empty()
+        && let Some(auto_build) = latest_auto_build(github, repo, pr_number).await?
+    {
+        jobs = failed_jobs(github, repo, &auto_build.merge_commit).await?;
+    }
+
+    let Some(job) = jobs.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let (excerpt, spurious) = match job_log(github, repo, job.id).await {
+        Ok(log) => (log_excerpt(&log), spurious_failures.classify(repo, &log)),
+        Err(e) => {
+            tracing::error!("error getting log of job {}: {e}", job.name);
+            (None, None)
+        }
+    };
+
+    Ok(Some(CiFailure {
+        job_name: job.name,
+        job_url: job.html_url,
+        excerpt,
+        spurious,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::log_excerpt;
+
+    const COMPILER_ERROR: &str = include_str!("fixtures/job_log.txt");
+    const TEST_FAILURES: &str = include_str!("fixtures/job_log_tests.txt");
+
+    #[test]
+    fn excerpt_is_the_first_compiler_error() {
+        let excerpt = log_excerpt(COMPILER_ERROR).unwrap();
+
+        assert!(excerpt.starts_with("error[E0308]: mismatched types\n"));
+        assert!(excerpt.contains("expected `u32`, found `Local`"));
+        assert!(!excerpt.contains("For more information"));
+        assert!(!excerpt.contains("2026-03-02T"));
+    }
+
+    #[test]
+    fn excerpt_lists_failing_tests() {
+        assert_eq!(
+            log_excerpt(TEST_FAILURES).unwrap(),
+            "failing tests:\ntests::parses_nested\ntests::roundtrip"
+        );
+    }
+
+    #[test]
+    fn excerpt_falls_back_to_failed() {
+        let log = "setting up\nrunning tidy\ntidy check FAILED\ndone";
+        let excerpt = log_excerpt(log).unwrap();
+
+        assert!(excerpt.starts_with("setting up\n"));
+        assert!(excerpt.contains("tidy check FAILED"));
+    }
+
+    #[test]
+    fn no_excerpt_without_failure() {
+        assert_eq!(log_excerpt("all good\nfinished"), None);
+    }
+}
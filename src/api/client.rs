@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use color_eyre::eyre::Context;
+use octocrab::Octocrab;
+
+use crate::api::rate_limit::{RateLimited, RateLimits, rate_limit};
+
+/// A user's GitHub client. Every request goes through [`GitHubClient::request`], so they're all
+/// throttled together, and whenever GitHub says to slow down all of them back off.
+#[derive(Clone, Debug)]
+pub struct GitHubClient {
+    octocrab: Octocrab,
+    /// Whose token this is, rate limits are per token
+    username: String,
+    rate_limits: Arc<RateLimits>,
+}
+
+impl GitHubClient {
+    pub fn new(octocrab: Octocrab, username: String, rate_limits: Arc<RateLimits>) -> Self {
+        Self {
+            octocrab,
+            username,
+            rate_limits,
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Fails right away with [`RateLimited`] during a backoff, otherwise waits for one of the
+    /// user's requests in flight to finish if there are too many
+    pub async fn request<T, F: Future<Output = color_eyre::Result<T>>>(
+        &self,
+        request: impl FnOnce(Octocrab) -> F,
+    ) -> color_eyre::Result<T> {
+        // without backing off again, the backoff isn't a new rate limit
+        if let Some(retry_after) = self.rate_limits.backoff_left(&self.username).await {
+            return Err(RateLimited {
+                retry_after: Some(retry_after),
+            })
+            .with_context(|| format!("backing off from the rate limit of {}", self.username));
+        }
+
+        let permits = self.rate_limits.permits(&self.username).await;
+        let _permit = permits.acquire().await?;
+
+        let res = request(self.octocrab.clone()).await;
+        if let Err(e) = &res
+            && let Some(rate_limited) = rate_limit(e)
+        {
+            self.rate_limits
+                .back_off(&self.username, rate_limited.retry_after)
+                .await;
+        }
+
+        res
+    }
+}
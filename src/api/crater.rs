@@ -1,50 +1,208 @@
 use std::collections::HashMap;
 
 use color_eyre::eyre::Context;
-use jiff::Timestamp;
+use futures::future;
+use jiff::{SignedDuration, Timestamp};
 use scraper::{ElementRef, Html, Selector};
+use url::Url;
 
-use crate::model::CraterStatus;
+use crate::{
+    api::{client::GitHubClient, github::recent_comments},
+    model::{CraterStatus, Repo},
+};
 
 const URL: &str = "https://crater.rust-lang.org/";
 
-pub async fn get_crater_queue() -> color_eyre::Result<HashMap<u64, CraterStatus>> {
-    let mut results = HashMap::new();
-    let response = reqwest::get(URL).await?;
+const CRATERBOT: &str = "craterbot";
+
+/// Used to estimate when queued experiments start if no running experiment tells us better
+const TYPICAL_RUN_LENGTH: SignedDuration = SignedDuration::from_hours(24 * 3);
+
+/// Details from the page of a single experiment
+#[derive(Default)]
+struct ExperimentDetails {
+    progress: Option<u8>,
+    started: Option<Timestamp>,
+    expected_end: Option<Timestamp>,
+}
+
+/// Crater renders dates client side from a `data-date` attribute
+fn parse_date(cell: ElementRef) -> Option<Timestamp> {
+    let date_selector = Selector::parse("[data-date]").unwrap();
+
+    let text = cell.text().collect::<String>();
+
+    cell.select(&date_selector)
+        .filter_map(|i| i.attr("data-date"))
+        .chain(cell.attr("data-date"))
+        .chain([text.trim()])
+        .find_map(|i| i.parse::<Timestamp>().ok())
+}
+
+/// Parses `45%` or `Running (45%)`
+fn parse_progress(text: &str) -> Option<u8> {
+    let (number, _) = text.split_once('%')?;
+    let number = number
+        .rsplit(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?;
+    number.parse::<f64>().ok().map(|i| i as u8)
+}
+
+/// The `table.details` rows of `/ex/<name>`
+fn parse_experiment_page(body: &str) -> ExperimentDetails {
+    let document = Html::parse_document(body);
+    let mut res = ExperimentDetails::default();
+
+    let row_selector = Selector::parse("table.details tr").unwrap();
+    let th_selector = Selector::parse("th").unwrap();
+    let td_selector = Selector::parse("td").unwrap();
+
+    for row in document.select(&row_selector) {
+        let (Some(header), Some(cell)) = (
+            row.select(&th_selector).next(),
+            row.select(&td_selector).next(),
+        ) else {
+            continue;
+        };
+
+        match header.text().collect::<String>().trim() {
+            "Progress:" => res.progress = parse_progress(&cell.text().collect::<String>()),
+            "Started at:" => res.started = parse_date(cell),
+            "Estimated end:" => res.expected_end = parse_date(cell),
+            _ => {}
+        }
+    }
+
+    res
+}
+
+async fn get_experiment_details(name: &str) -> color_eyre::Result<ExperimentDetails> {
+    let response = reqwest::get(format!("{URL}ex/{name}"))
+        .await
+        .context("get experiment")?;
     let body = response.text().await.context("body")?;
 
-    let document = Html::parse_document(&body);
-    let mut number_in_queue = 0;
+    Ok(parse_experiment_page(&body))
+}
+
+/// Guess when a running experiment ends, preferring the ETA the crater agents report
+/// and otherwise extrapolating from the progress so far.
+fn expected_end(details: &ExperimentDetails) -> Option<Timestamp> {
+    if let Some(expected_end) = details.expected_end {
+        return Some(expected_end);
+    }
+
+    let started = details.started?;
+    let progress = details.progress.filter(|i| *i > 0)?;
+    let elapsed = Timestamp::now().duration_since(started);
+
+    started
+        .checked_add(elapsed.mul_f64(100.0 / progress as f64))
+        .ok()
+}
+
+/// Queued and running experiments from the list on crater's front page, as
+/// (pr number, experiment name, status text). Rows we can't read are skipped.
+fn parse_queue_page(body: &str) -> Vec<(u64, String, String)> {
+    let document = Html::parse_document(body);
+    let mut rows = Vec::new();
 
     let row_selector = Selector::parse("table.list tbody tr").unwrap();
-    for row in document.select(&row_selector) {
+    for (idx, row) in document.select(&row_selector).enumerate() {
         let children = row
             .children()
             .filter_map(ElementRef::wrap)
             .collect::<Vec<_>>();
 
-        let number = children[0].text().collect::<String>();
-        let status = children[5].text().collect::<String>();
+        let (Some(name), Some(status)) = (children.first(), children.get(5)) else {
+            tracing::warn!(
+                "skipping crater queue row {}: {} cells",
+                idx + 1,
+                children.len()
+            );
+            continue;
+        };
+        let name = name.text().collect::<String>();
+        let status = status.text().collect::<String>();
 
         // header row
-        if number == "Name" {
+        if name == "Name" {
             continue;
         }
 
-        let Ok(number) = number.trim().trim_start_matches("pr-").parse::<u64>() else {
-            tracing::error!("parse PR number: {}", number.trim().trim_end_matches("pr-"));
+        // reruns are named like pr-12345-1
+        let number = name
+            .trim()
+            .trim_start_matches("pr-")
+            .split('-')
+            .next()
+            .unwrap_or_default();
+
+        let Ok(number) = number.parse::<u64>() else {
+            tracing::error!("parse PR number: {}", name.trim());
             continue;
         };
 
-        let status = match status.trim() {
-            x if x.starts_with("Running") => CraterStatus::Running {
-                expected_end: Timestamp::now(),
-            },
+        rows.push((number, name.trim().to_string(), status.trim().to_string()));
+    }
+
+    rows
+}
+
+pub async fn get_crater_queue() -> color_eyre::Result<HashMap<u64, CraterStatus>> {
+    let mut results = HashMap::new();
+    let response = reqwest::get(URL).await?;
+    let body = response.text().await.context("body")?;
+
+    let rows = parse_queue_page(&body);
+
+    // the pages of the running experiments, all at once
+    let mut details = future::join_all(
+        rows.iter()
+            .filter(|(_, _, status)| status.starts_with("Running"))
+            .map(|(_, name, _)| async move {
+                let details = match get_experiment_details(name).await {
+                    Ok(i) => i,
+                    Err(e) => {
+                        tracing::error!("crater experiment {name} error: {e}");
+                        Default::default()
+                    }
+                };
+                (name.clone(), details)
+            }),
+    )
+    .await
+    .into_iter()
+    .collect::<HashMap<_, _>>();
+
+    let mut number_in_queue = 0;
+    // when the experiments that are currently running are expected to end
+    let mut running_ends = Vec::new();
+    // how long the experiments that are currently running take in total
+    let mut running_lengths = Vec::new();
+
+    for (number, name, status) in rows {
+        let status = match status.as_str() {
+            x if x.starts_with("Running") => {
+                let details = details.remove(&name).unwrap_or_default();
+
+                let expected_end = expected_end(&details);
+                running_ends.extend(expected_end);
+                if let (Some(started), Some(expected_end)) = (details.started, expected_end) {
+                    running_lengths.push(expected_end.duration_since(started));
+                }
+
+                CraterStatus::Running {
+                    expected_end,
+                    progress: details.progress.or_else(|| parse_progress(x)),
+                }
+            }
             "Generating report" => CraterStatus::GeneratingReport,
             "Queued" => {
                 number_in_queue += 1;
                 CraterStatus::Queued {
-                    num_before: number_in_queue,
+                    num_before: number_in_queue - 1,
+                    expected_start: None,
                 }
             }
             other => {
@@ -55,5 +213,192 @@ pub async fn get_crater_queue() -> color_eyre::Result<HashMap<u64, CraterStatus>
 
         results.insert(number, status);
     }
+
+    // queued experiments start one after another once the running ones are done
+    let queue_start = running_ends
+        .into_iter()
+        .max()
+        .unwrap_or_else(Timestamp::now);
+    let run_length = if running_lengths.is_empty() {
+        TYPICAL_RUN_LENGTH
+    } else {
+        running_lengths.iter().sum::<SignedDuration>() / running_lengths.len() as i32
+    };
+
+    for status in results.values_mut() {
+        if let CraterStatus::Queued {
+            num_before,
+            expected_start,
+        } = status
+        {
+            *expected_start = queue_start
+                .checked_add(run_length * *num_before as i32)
+                .ok();
+        }
+    }
+
     Ok(results)
 }
+
+/// Every category of the report has a header like `regressed (3)`
+fn parse_report_index(report_url: Url, body: &str) -> CraterStatus {
+    let document = Html::parse_document(body);
+    let header_selector = Selector::parse(".category > .header").unwrap();
+
+    let mut counts = HashMap::new();
+    for header in document.select(&header_selector) {
+        let text = header.text().collect::<String>();
+        let Some((category, count)) = text.trim().rsplit_once(" (") else {
+            continue;
+        };
+        let Ok(count) = count.trim_end_matches(')').parse::<usize>() else {
+            tracing::error!("weird crater report category: '{}'", text.trim());
+            continue;
+        };
+
+        *counts.entry(category.to_string()).or_default() += count;
+    }
+
+    let count = |category: &str| counts.get(category).copied().unwrap_or(0);
+
+    CraterStatus::Completed {
+        report_url,
+        regressed: count("regressed"),
+        fixed: count("fixed"),
+        spurious: count("spurious-regressed") + count("spurious-fixed"),
+    }
+}
+
+/// The results of a finished experiment, from the report craterbot linked on the PR
+pub async fn get_crater_report(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_number: u64,
+) -> color_eyre::Result<Option<CraterStatus>> {
+    let comments = recent_comments(github, repo, pr_number)
+        .await
+        .context("get comments")?;
+
+    // ":tada: Experiment `pr-12345` is completed! ... [Open the summary report](<url>)"
+    let Some(report_url) = comments
+        .iter()
+        .rev()
+        .filter(|c| c.user.login == CRATERBOT)
+        .filter_map(|c| c.body.as_deref())
+        .filter(|body| body.contains("is completed"))
+        .find_map(|body| {
+            let (_, rest) = body.split_once("[Open the summary report](")?;
+            let (url, _) = rest.split_once(')')?;
+            Url::parse(url).ok()
+        })
+    else {
+        return Ok(None);
+    };
+
+    let response = reqwest::get(report_url.clone())
+        .await
+        .context("get crater report")?;
+    let body = response.text().await.context("body")?;
+
+    Ok(Some(parse_report_index(report_url, &body)))
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::Timestamp;
+    use url::Url;
+
+    use super::{
+        expected_end, parse_experiment_page, parse_progress, parse_queue_page, parse_report_index,
+    };
+    use crate::model::CraterStatus;
+
+    const EXPERIMENT: &str = include_str!("fixtures/crater_experiment.html");
+    const REPORT: &str = include_str!("fixtures/crater_report.html");
+
+    #[test]
+    fn experiment_page_is_parsed() {
+        let details = parse_experiment_page(EXPERIMENT);
+
+        assert_eq!(details.progress, Some(47));
+        assert_eq!(
+            details.started,
+            Some("2026-03-02T14:00:00Z".parse::<Timestamp>().unwrap())
+        );
+        assert_eq!(
+            details.expected_end,
+            Some("2026-03-04T20:30:00Z".parse::<Timestamp>().unwrap())
+        );
+        assert_eq!(expected_end(&details), details.expected_end);
+    }
+
+    #[test]
+    fn experiment_page_without_estimate() {
+        let body = EXPERIMENT.replace("Estimated end:", "Completed at:");
+        let details = parse_experiment_page(&body);
+
+        assert_eq!(details.expected_end, None);
+        // extrapolated from the progress, so after the start
+        assert!(expected_end(&details).unwrap() > details.started.unwrap());
+    }
+
+    #[test]
+    fn progress_from_list_status() {
+        assert_eq!(parse_progress("Running (45%)"), Some(45));
+        assert_eq!(parse_progress("12.5%"), Some(12));
+        assert_eq!(parse_progress("Queued"), None);
+    }
+
+    #[test]
+    fn short_queue_rows_are_skipped() {
+        let body = r#"
+            <table class="list"><tbody>
+                <tr><th>Name</th><th>Mode</th><th>Assigned to</th><th>Priority</th><th>Progress</th><th>Status</th></tr>
+                <tr><td>pr-140001</td><td>check-only</td><td>agent</td><td>0</td><td>47%</td><td>Running (47%)</td></tr>
+                <tr><td>pr-140002</td><td>Queued</td></tr>
+                <tr><td>pr-140003-1</td><td>build-and-test</td><td></td><td>0</td><td></td><td>Queued</td></tr>
+            </tbody></table>
+        "#;
+
+        assert_eq!(
+            parse_queue_page(body),
+            [
+                (140001, "pr-140001".to_string(), "Running (47%)".to_string()),
+                (140003, "pr-140003-1".to_string(), "Queued".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn report_index_is_parsed() {
+        let report_url =
+            Url::parse("https://crater-reports.s3.amazonaws.com/pr-140001/index.html").unwrap();
+
+        assert_eq!(
+            parse_report_index(report_url.clone(), REPORT),
+            CraterStatus::Completed {
+                report_url,
+                regressed: 3,
+                fixed: 1,
+                spurious: 6,
+            }
+        );
+    }
+
+    #[test]
+    fn report_without_categories_has_no_counts() {
+        let report_url =
+            Url::parse("https://crater-reports.s3.amazonaws.com/pr-140001/index.html").unwrap();
+        let status = parse_report_index(report_url, "<h1>Crater report for pr-140001</h1>");
+
+        assert!(matches!(
+            status,
+            CraterStatus::Completed {
+                regressed: 0,
+                fixed: 0,
+                spurious: 0,
+                ..
+            }
+        ));
+    }
+}
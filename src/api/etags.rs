@@ -0,0 +1,98 @@
+use color_eyre::eyre::{Context, eyre};
+use octocrab::{etag::EntityTag, map_github_error, models::pulls::PullRequest};
+use reqwest::{StatusCode, header::HeaderMap};
+
+use crate::{
+    api::{PrCache, client::GitHubClient, rate_limit::RateLimited},
+    model::{GitHubPr, Repo},
+};
+
+/// PRs we got through the REST API, with the ETag GitHub sent along with them. Unused ones are
+/// dropped like from any `PrCache`, the ones a user still has are kept in the database with the
+/// rest of their refresh state.
+pub struct Etags(PrCache<(EntityTag, GitHubPr), ()>);
+
+impl Etags {
+    pub fn new() -> Self {
+        Self(PrCache::new())
+    }
+
+    /// A conditional GET. When nothing changed GitHub answers with a 304, which doesn't count
+    /// against the rate limit, and we use the PR we got last time.
+    pub async fn get_pr(
+        &self,
+        github: &GitHubClient,
+        repo: &Repo,
+        number: u64,
+    ) -> color_eyre::Result<GitHubPr> {
+        let route = format!("/repos/{}/{}/pulls/{number}", repo.owner, repo.name);
+        let cached = self.0.get(repo, number, &()).await;
+
+        let mut headers = HeaderMap::new();
+        if let Some((etag, _)) = &cached {
+            EntityTag::insert_if_none_match_header(&mut headers, etag.clone())?;
+        }
+
+        let (etag, body) = github
+            .request(async |octocrab| {
+                let response = octocrab
+                    ._get_with_headers(route.as_str(), Some(headers))
+                    .await
+                    .with_context(|| format!("get {route}"))?;
+                if response.status() == StatusCode::NOT_MODIFIED {
+                    return Ok((None, None));
+                }
+
+                // raw responses aren't turned into errors by octocrab itself
+                if let Some(rate_limited) =
+                    RateLimited::from_response(response.status(), response.headers())
+                {
+                    return Err(rate_limited.into());
+                }
+                let response = map_github_error(response)
+                    .await
+                    .with_context(|| format!("get {route}"))?;
+                let etag = EntityTag::extract_from_response(&response);
+                let body = octocrab
+                    .body_to_string(response)
+                    .await
+                    .context("response body")?;
+
+                Ok((etag, Some(body)))
+            })
+            .await?;
+
+        let body = match (body, cached) {
+            (Some(body), _) => body,
+            (None, Some((_, pr))) => return Ok(pr),
+            (None, None) => return Err(eyre!("get {route}: not modified, but nothing cached")),
+        };
+
+        let pr: PullRequest =
+            serde_json::from_str(&body).with_context(|| format!("parse {route}"))?;
+        let pr = GitHubPr::try_from(pr)?;
+        if let Some(etag) = etag {
+            self.0.insert(repo, number, (), (etag, pr.clone())).await;
+        }
+
+        Ok(pr)
+    }
+
+    /// The ETag of a PR as it was last time, if we got it through the REST API
+    pub async fn etag(&self, repo: &Repo, pr: &GitHubPr) -> Option<String> {
+        let (etag, cached) = self.0.get(repo, pr.issue.number, &()).await?;
+        (cached.issue.updated_at == pr.issue.updated_at).then(|| etag.to_string())
+    }
+
+    /// For a PR loaded from the database, with the ETag from `etag`
+    pub async fn restore(&self, repo: &Repo, pr: &GitHubPr, etag: &str) {
+        match etag.parse() {
+            Ok(etag) => {
+                self.0
+                    .insert(repo, pr.issue.number, (), (etag, pr.clone()))
+                    .await
+            }
+            Err(e) => tracing::error!("stored etag of {repo}#{}: {e}", pr.issue.number),
+        }
+    }
+}
@@ -1,24 +1,107 @@
-use std::{future::ready, sync::Arc, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    future::ready,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
-use color_eyre::eyre::Context;
+use color_eyre::eyre::{Context, eyre};
 use futures::{SinkExt, StreamExt, stream};
+use jiff::{SignedDuration, Timestamp};
 use octocrab::{
-    Octocrab,
-    models::{issues::Issue, pulls::PullRequest},
+    models::{
+        issues::{Comment, Issue},
+        pulls::{MergeableState, PullRequest},
+    },
     params,
 };
 use reqwest::StatusCode;
-use tokio::{spawn, time::sleep};
+use serde::{Deserialize, Serialize};
+use tokio::{spawn, sync::Mutex, time::sleep};
 
 use futures::channel::mpsc::channel;
-use url::Url;
 
 use crate::{
+    api::{
+        PrCache,
+        bors::BorsStatus,
+        client::GitHubClient,
+        graphql::{prs, updated_at, user_prs},
+        rate_limit::rate_limit,
+    },
     login_cx::LoginContext,
-    model::{Author, Pr, Repo},
+    model::{
+        Author, Check, CheckConclusion, CraterStatus, FcpStatus, GitHubIssue, GitHubPr, PerfStatus,
+        Pr, Repo, RepoInfo,
+    },
     sort::{PredeterminedCategory, convert_author, sort},
 };
 
+impl From<&Issue> for GitHubIssue {
+    fn from(issue: &Issue) -> Self {
+        GitHubIssue {
+            number: issue.number,
+            title: issue.title.clone(),
+            body: issue.body.clone(),
+            url: issue.html_url.clone(),
+            author: convert_author(&issue.user),
+            assignees: issue.assignees.iter().map(convert_author).collect(),
+            labels: issue.labels.iter().map(|i| i.name.clone()).collect(),
+            created_at: Timestamp::from_second(issue.created_at.timestamp()).unwrap(),
+            updated_at: Timestamp::from_second(issue.updated_at.timestamp()).unwrap(),
+        }
+    }
+}
+
+impl TryFrom<PullRequest> for GitHubPr {
+    type Error = color_eyre::Report;
+
+    /// Most fields of a REST PR are optional, but are always there for open PRs
+    fn try_from(pr: PullRequest) -> color_eyre::Result<Self> {
+        let missing = |field| eyre!("PR #{} has no {field}", pr.number);
+
+        Ok(GitHubPr {
+            issue: GitHubIssue {
+                number: pr.number,
+                title: pr.title.clone().ok_or_else(|| missing("title"))?,
+                body: pr.body.clone(),
+                url: pr.html_url.clone().ok_or_else(|| missing("url"))?,
+                author: convert_author(pr.user.as_deref().ok_or_else(|| missing("author"))?),
+                assignees: pr.assignees.iter().flatten().map(convert_author).collect(),
+                labels: pr.labels.iter().flatten().map(|i| i.name.clone()).collect(),
+                created_at: Timestamp::from_second(
+                    pr.created_at
+                        .ok_or_else(|| missing("creation date"))?
+                        .timestamp(),
+                )?,
+                updated_at: Timestamp::from_second(
+                    pr.updated_at
+                        .ok_or_else(|| missing("update date"))?
+                        .timestamp(),
+                )?,
+            },
+            head_sha: pr.head.sha.clone(),
+            requested_reviewers: pr
+                .requested_reviewers
+                .iter()
+                .flatten()
+                .map(convert_author)
+                .collect(),
+            draft: pr.draft.unwrap_or(false),
+            conflicted: matches!(
+                pr.mergeable_state,
+                Some(MergeableState::Behind | MergeableState::Dirty)
+            ),
+            private: pr
+                .base
+                .repo
+                .as_ref()
+                .and_then(|i| i.private)
+                .unwrap_or(true),
+        })
+    }
+}
+
 pub enum PrSource {
     Subscribed,
     Direct,
@@ -33,25 +116,24 @@ async fn try_username_suggestions(
         let res = reqwest::get(&url).await.context("reqwest")?;
 
         return Ok(if res.status() == StatusCode::OK {
-            vec![Author {
-                name: current,
-                id: 0,
-                avatar_url: Url::parse(&format!("{url}.png")).unwrap(),
-                profile_url: Url::parse(&url).unwrap(),
-            }]
+            vec![Author::from_username(current)]
         } else {
             vec![]
         });
     }
 
     let res = login_context
-        .octocrab
-        .search()
-        .users(&format!("{current} type:user"))
-        .per_page(10)
-        .send()
-        .await
-        .context("search request")?;
+        .github
+        .request(async |octocrab| {
+            octocrab
+                .search()
+                .users(&format!("{current} type:user"))
+                .per_page(10)
+                .send()
+                .await
+                .context("search request")
+        })
+        .await?;
 
     Ok(res.items.iter().map(convert_author).collect())
 }
@@ -69,87 +151,655 @@ pub async fn username_suggestions(
     }
 }
 
+/// Everything sorting a PR depends on that can change. A PR with the same fingerprint as at
+/// the last refresh keeps the `Pr` it was sorted into back then.
+#[derive(Clone, PartialEq)]
+pub struct PrFingerprint {
+    updated_at: Timestamp,
+    head_sha: Option<String>,
+    /// None when the checks didn't come along with the PR, so we can't tell whether they changed
+    checks: Option<Vec<(String, CheckConclusion)>>,
+    /// PRs in the bors queue move along with it, those are always sorted again
+    in_bors_queue: bool,
+    crater: Option<CraterStatus>,
+    perf: Option<PerfStatus>,
+    fcp: Option<FcpStatus>,
+}
+
+impl PrFingerprint {
+    async fn new(
+        login_context: &LoginContext,
+        repo: &RepoInfo,
+        issue: &GitHubIssue,
+        predetermined_category: &PredeterminedCategory,
+        checks: Option<&[Check]>,
+    ) -> Self {
+        let bors_for_repo = login_context.state.bors_info(repo.clone()).await;
+        let crater_info = login_context.state.crater_info.get().await;
+        let perf_info = login_context.state.perf_info.get().await;
+        let fcp_info = login_context.state.fcp_info.get().await;
+
+        Self {
+            updated_at: issue.updated_at,
+            head_sha: match predetermined_category {
+                PredeterminedCategory::None(pr) => Some(pr.head_sha.clone()),
+                PredeterminedCategory::Subscribed => None,
+            },
+            checks: checks.map(|checks| {
+                checks
+                    .iter()
+                    .map(|i| (i.name.clone(), i.conclusion.clone()))
+                    .collect()
+            }),
+            in_bors_queue: bors_for_repo
+                .for_pr(issue.number)
+                .is_some_and(|i| i.status != BorsStatus::None),
+            crater: crater_info.get(&issue.number).cloned(),
+            perf: perf_info.get(&issue.number).cloned(),
+            fcp: fcp_info
+                .get(&(repo.repo.clone(), issue.number))
+                .map(|i| i.status()),
+        }
+    }
+
+    /// Whether `pr`, sorted when its fingerprint was `old`, is still up to date
+    fn can_reuse(&self, old: &PrFingerprint, pr: &Pr) -> bool {
+        self == old && self.checks.is_some() && !self.in_bors_queue && !pr.in_progress()
+    }
+}
+
+/// The PRs of the last refresh, with what they were sorted based on. Only kept in memory, so
+/// after a restart every PR is sorted again, though not every PR is fetched again: see
+/// `RefreshState`.
+pub type PreviousPrs = HashMap<(Repo, u64), (PrFingerprint, Pr)>;
+
+/// PRs are listed as updated since a bit before the last listing started, in case GitHub's clock
+/// is behind ours
+const LISTED_SINCE_MARGIN: SignedDuration = SignedDuration::from_mins(5);
+
+/// When the REST fallback checks whether PRs that weren't listed again are unchanged, this many
+/// of them at a time. Mostly they're 304s, the client throttles the rest.
+const UNLISTED_CONCURRENCY: usize = 8;
+
+/// The PRs of a user in a repo as of the last time they were listed, so next time only the ones
+/// updated since have to be listed and fetched
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RepoRefresh {
+    pub repo: Repo,
+    /// When the listing started
+    pub listed_at: Timestamp,
+    /// With the ETag of the ones we got through the REST API
+    pub prs: Vec<(GitHubPr, Option<String>)>,
+}
+
+/// What the last refresh of a user saw in each repo, the logged in user's is kept in the
+/// database. A repo that couldn't be listed keeps what it had.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct RefreshState(pub Vec<RepoRefresh>);
+
+impl RefreshState {
+    fn repo(&self, repo: &Repo) -> Option<&RepoRefresh> {
+        self.0.iter().find(|i| i.repo == *repo)
+    }
+
+    fn set(&mut self, refresh: RepoRefresh) {
+        self.0.retain(|i| i.repo != refresh.repo);
+        self.0.push(refresh);
+    }
+}
+
+/// Keeps what a listing saw for the next refresh
+async fn remember(
+    login_context: &LoginContext,
+    refresh: &Mutex<RefreshState>,
+    repo: &Repo,
+    listed_at: Timestamp,
+    prs: Vec<GitHubPr>,
+) {
+    let mut stored = Vec::new();
+    for pr in prs {
+        let etag = login_context.state.etags.etag(repo, &pr).await;
+        stored.push((pr, etag));
+    }
+
+    refresh.lock().await.set(RepoRefresh {
+        repo: repo.clone(),
+        listed_at,
+        prs: stored,
+    });
+}
+
+/// The open PRs of a user in a repo. Once listed, only the ones updated since are searched for
+/// again. The others are taken from `stored` as long as they weren't updated, since being closed
+/// or unassigned counts as an update too.
+async fn list_prs(
+    login_context: &LoginContext,
+    repo: &Repo,
+    username: &str,
+    stored: Option<&RepoRefresh>,
+) -> color_eyre::Result<Vec<GitHubPr>> {
+    let github = &login_context.github;
+    let since = stored.map(|i| i.listed_at - LISTED_SINCE_MARGIN);
+    let listed = user_prs(github, repo, username, since).await?;
+
+    let mut unchanged = Vec::new();
+    if let Some(stored) = stored {
+        let others = stored
+            .prs
+            .iter()
+            .map(|(pr, _)| pr)
+            .filter(|pr| !listed.contains_key(&pr.issue.number))
+            .collect::<Vec<_>>();
+        let numbers = others.iter().map(|pr| pr.issue.number).collect::<Vec<_>>();
+        let updated_at = updated_at(github, repo, &numbers).await?;
+
+        unchanged.extend(
+            others
+                .into_iter()
+                .filter(|pr| updated_at.get(&pr.issue.number) == Some(&pr.issue.updated_at))
+                .cloned(),
+        );
+    }
+
+    let state = &login_context.state;
+    let listed = prs_updated_at(github, &state.shared_prs, &state.checks, repo, listed).await?;
+
+    Ok(listed.into_values().chain(unchanged).collect())
+}
+
+/// The checks we already have for a PR, as long as none are still running
+async fn finished_checks(
+    login_context: &LoginContext,
+    repo: &Repo,
+    pr: &GitHubPr,
+) -> Option<Vec<Check>> {
+    let checks = login_context
+        .state
+        .checks
+        .get(repo, pr.issue.number, &pr.head_sha)
+        .await?;
+
+    (!checks
+        .iter()
+        .any(|i| i.conclusion == CheckConclusion::Pending))
+    .then_some(checks)
+}
+
+enum Fetched {
+    Pr {
+        issue: Box<GitHubIssue>,
+        repo: RepoInfo,
+        predetermined_category: Box<PredeterminedCategory>,
+        /// Only when they came along with the PR
+        checks: Option<Vec<Check>>,
+    },
+    /// Rate limited, so we show what we had
+    Previous(Box<(PrFingerprint, Pr)>),
+}
+
+impl Fetched {
+    fn pr(repo: RepoInfo, pr: GitHubPr, checks: Option<Vec<Check>>) -> Self {
+        Fetched::Pr {
+            issue: Box::new(pr.issue.clone()),
+            repo,
+            predetermined_category: Box::new(PredeterminedCategory::None(Box::new(pr))),
+            checks,
+        }
+    }
+}
+
+/// What the last refresh had for a repo, for when it can't be listed now
+fn previous_in_repo(previous: &PreviousPrs, repo: &Repo) -> Vec<Fetched> {
+    previous
+        .values()
+        .filter(|(_, pr)| pr.repo == *repo)
+        .map(|(fingerprint, pr)| Fetched::Previous(Box::new((fingerprint.clone(), pr.clone()))))
+        .collect()
+}
+
+/// Only lists PRs updated since the last refresh, as far as `refresh` knows, and only sorts PRs
+/// that changed since the last refresh, the others are taken from `previous`
 pub fn scrape_github_for_user(
     login_context: Arc<LoginContext>,
     username: String,
-) -> impl StreamExt<Item = Pr> {
+    previous: Arc<PreviousPrs>,
+    refresh: Arc<Mutex<RefreshState>>,
+) -> impl StreamExt<Item = (PrFingerprint, Pr)> {
     stream::iter(login_context.repos.clone())
-        // for each repo
-        .map({
+        // for each repo, the PRs from github
+        .then({
             let login_context = login_context.clone();
             let username = username.clone();
+            let previous = previous.clone();
             move |repo| {
-                // all assigned issues
-                assigned_issues(repo.repo.clone(), username.clone(), login_context.clone())
-                    // and all own issues
-                    .chain(own_issues(
-                        repo.repo.clone(),
-                        username.clone(),
-                        login_context.clone(),
-                    ))
-                    // .chain(subscribed_issues(repo.repo.clone(), login_context.clone()))
-                    .zip(stream::repeat(repo))
-            }
-        })
-        // flattened
-        .flatten()
-        // only the issues that are actually PRs
-        .filter(|((issue, _), _)| ready(issue.pull_request.is_some()))
-        // get their PR object from github
-        .map({
-            let login_context = login_context.clone();
-            move |((issue, source), repo)| {
                 let login_context = login_context.clone();
+                let username = username.clone();
+                let previous = previous.clone();
+                let refresh = refresh.clone();
 
                 async move {
-                    match source {
-                        PrSource::Subscribed => {
-                            Some((issue, repo, PredeterminedCategory::Subscribed))
+                    let stored = refresh.lock().await.repo(&repo.repo).cloned();
+                    let listed_at = Timestamp::now();
+
+                    match list_prs(&login_context, &repo.repo, &username, stored.as_ref()).await {
+                        Ok(prs) => {
+                            login_context
+                                .state
+                                .scrape_health
+                                .succeeded(
+                                    login_context.github.username(),
+                                    &username,
+                                    &repo.repo,
+                                    prs.len(),
+                                    Vec::new(),
+                                )
+                                .await;
+                            remember(&login_context, &refresh, &repo.repo, listed_at, prs.clone())
+                                .await;
+
+                            // fetched PRs came with their checks, which were cached
+                            let mut fetched = Vec::new();
+                            for pr in prs {
+                                let checks = finished_checks(&login_context, &repo.repo, &pr).await;
+                                fetched.push(Fetched::pr(repo.clone(), pr, checks));
+                            }
+
+                            stream::iter(fetched).boxed()
                         }
-                        PrSource::Direct => {
-                            match get_pr(&login_context.octocrab, repo.repo.clone(), issue.number)
-                                .await
+                        // falling back to REST would only make it worse, the client already
+                        // backs off, and fails right away while it does
+                        Err(e) if rate_limit(&e).is_some() => {
+                            login_context
+                                .state
+                                .scrape_health
+                                .failed(login_context.github.username(), &username, &repo.repo, &e)
+                                .await;
+
+                            stream::iter(previous_in_repo(&previous, &repo.repo)).boxed()
+                        }
+                        Err(e) => {
+                            tracing::error!("error getting PRs through graphql, using REST: {e}");
+
+                            // only once REST is done do we know whether it worked
+                            let mut prs = Vec::new();
+                            let mut error = None;
+                            let since = stored.as_ref().map(|i| i.listed_at - LISTED_SINCE_MARGIN);
+                            for i in rest_prs(
+                                login_context.clone(),
+                                username.clone(),
+                                repo.clone(),
+                                since,
+                            )
+                            .collect::<Vec<_>>()
+                            .await
                             {
-                                Ok(pr) => Some((issue, repo, PredeterminedCategory::None(pr))),
-                                Err(e) => {
-                                    tracing::error!("error getting PR: {e}");
-                                    None
+                                match i {
+                                    Ok(pr) => prs.push(pr),
+                                    Err(e) => error = Some(e),
+                                }
+                            }
+
+                            // the ones that weren't listed again are unchanged if they weren't
+                            // updated, which a conditional request tells without costing anything
+                            if let Some(stored) = &stored
+                                && error.is_none()
+                            {
+                                let listed = prs
+                                    .iter()
+                                    .map(|(issue, _, _)| issue.number)
+                                    .collect::<HashSet<_>>();
+                                match unchanged_unlisted(
+                                    &login_context,
+                                    &username,
+                                    &repo.repo,
+                                    stored,
+                                    &listed,
+                                )
+                                .await
+                                {
+                                    Ok(unchanged) => prs.extend(unchanged.into_iter().map(|pr| {
+                                        (
+                                            pr.issue.clone(),
+                                            repo.clone(),
+                                            PredeterminedCategory::None(Box::new(pr)),
+                                        )
+                                    })),
+                                    Err(e) => error = Some(e),
                                 }
                             }
+
+                            let scrape_health = &login_context.state.scrape_health;
+                            match error {
+                                Some(error) => {
+                                    scrape_health
+                                        .failed(
+                                            login_context.github.username(),
+                                            &username,
+                                            &repo.repo,
+                                            &error,
+                                        )
+                                        .await
+                                }
+                                None => {
+                                    let current = prs
+                                        .iter()
+                                        .filter_map(|(_, _, category)| match category {
+                                            PredeterminedCategory::None(pr) => Some((**pr).clone()),
+                                            PredeterminedCategory::Subscribed => None,
+                                        })
+                                        .collect();
+                                    remember(
+                                        &login_context,
+                                        &refresh,
+                                        &repo.repo,
+                                        listed_at,
+                                        current,
+                                    )
+                                    .await;
+
+                                    scrape_health
+                                        .succeeded(
+                                            login_context.github.username(),
+                                            &username,
+                                            &repo.repo,
+                                            prs.len(),
+                                            vec![format!(
+                                                "GraphQL failed, used REST instead: {e:#}"
+                                            )],
+                                        )
+                                        .await
+                                }
+                            }
+
+                            stream::iter(prs)
+                                .map(|(issue, repo, predetermined_category)| Fetched::Pr {
+                                    issue: Box::new(issue),
+                                    repo,
+                                    predetermined_category: Box::new(predetermined_category),
+                                    checks: None,
+                                })
+                                .boxed()
                         }
                     }
                 }
             }
         })
-        // paralellized
-        .buffer_unordered(100)
-        // filter out the ones where we couldn't get a PR object from github
-        .filter_map(|i| ready(i))
-        // sort them into our own data structures
-        .map(move |(issue, repo, predetermined_category)| {
+        // flattened
+        .flatten()
+        // sort the ones that changed into our own data structures
+        .map(move |fetched| {
             let login_context = login_context.clone();
             let username = username.clone();
+            let previous = previous.clone();
             async move {
-                sort(
+                let (issue, repo, predetermined_category, checks) = match fetched {
+                    Fetched::Pr {
+                        issue,
+                        repo,
+                        predetermined_category,
+                        checks,
+                    } => (*issue, repo, *predetermined_category, checks),
+                    Fetched::Previous(previous) => return Some(*previous),
+                };
+
+                let fingerprint = PrFingerprint::new(
+                    &login_context,
+                    &repo,
+                    &issue,
+                    &predetermined_category,
+                    checks.as_deref(),
+                )
+                .await;
+
+                if let Some((old, pr)) = previous.get(&(repo.repo.clone(), issue.number))
+                    && fingerprint.can_reuse(old, pr)
+                {
+                    return Some((fingerprint, pr.clone()));
+                }
+
+                if let (PredeterminedCategory::None(pr), Some(checks)) =
+                    (&predetermined_category, checks)
+                {
+                    login_context
+                        .state
+                        .checks
+                        .insert(&repo.repo, pr.issue.number, pr.head_sha.clone(), checks)
+                        .await;
+                }
+
+                let pr = sort(
                     &login_context,
                     username,
                     &repo,
                     &issue,
                     predetermined_category,
                 )
-                .await
+                .await?;
+
+                Some((fingerprint, pr))
             }
         })
         .buffer_unordered(100)
         .filter_map(|i| ready(i))
 }
 
+/// The PRs stored for a repo that weren't listed again, if they weren't updated since. Ones that
+/// were would've been listed if they were still the user's, so they're dropped.
+async fn unchanged_unlisted(
+    login_context: &LoginContext,
+    username: &str,
+    repo: &Repo,
+    stored: &RepoRefresh,
+    listed: &HashSet<u64>,
+) -> color_eyre::Result<Vec<GitHubPr>> {
+    let unlisted = stream::iter(
+        stored
+            .prs
+            .iter()
+            .map(|(pr, _)| pr)
+            .filter(|pr| !listed.contains(&pr.issue.number)),
+    )
+    .map(move |pr| async move {
+        let current = get_pr(login_context, repo.clone(), pr.issue.number, None).await;
+        (pr, current)
+    })
+    .buffer_unordered(UNLISTED_CONCURRENCY)
+    .collect::<Vec<_>>()
+    .await;
+
+    let mut res = Vec::new();
+    for (pr, current) in unlisted {
+        let current = current?;
+        if current.issue.updated_at == pr.issue.updated_at {
+            res.push(current);
+        } else {
+            tracing::info!(
+                "{repo}#{} of {username} was updated but not listed, dropping it",
+                pr.issue.number
+            );
+        }
+    }
+
+    Ok(res)
+}
+
+/// Lists the issues of a user in a repo, and then gets every PR among them. Listing the issues
+/// failing is an error, a single PR failing is only logged. With `since`, only the issues updated
+/// since then.
+fn rest_prs(
+    login_context: Arc<LoginContext>,
+    username: String,
+    repo: RepoInfo,
+    since: Option<Timestamp>,
+) -> impl StreamExt<Item = color_eyre::Result<(GitHubIssue, RepoInfo, PredeterminedCategory)>> {
+    // all assigned issues
+    assigned_issues(
+        repo.repo.clone(),
+        username.clone(),
+        since,
+        login_context.clone(),
+    )
+    // and all own issues
+    .chain(own_issues(
+        repo.repo.clone(),
+        username.clone(),
+        since,
+        login_context.clone(),
+    ))
+    // .chain(subscribed_issues(repo.repo.clone(), login_context.clone()))
+    .zip(stream::repeat(repo))
+    // get their PR object from github
+    .map(move |(issue, repo)| {
+        let login_context = login_context.clone();
+
+        async move {
+            let (issue, source) = issue?;
+            // only the issues that are actually PRs
+            if issue.pull_request.is_none() {
+                return Ok(None);
+            }
+            let issue = GitHubIssue::from(&issue);
+
+            match source {
+                PrSource::Subscribed => Ok(Some((issue, repo, PredeterminedCategory::Subscribed))),
+                PrSource::Direct => {
+                    match get_pr(
+                        &login_context,
+                        repo.repo.clone(),
+                        issue.number,
+                        Some(issue.updated_at),
+                    )
+                    .await
+                    {
+                        Ok(pr) => Ok(Some((
+                            issue,
+                            repo,
+                            PredeterminedCategory::None(Box::new(pr)),
+                        ))),
+                        Err(e) => {
+                            tracing::error!("error getting PR: {e}");
+                            Ok(None)
+                        }
+                    }
+                }
+            }
+        }
+    })
+    // paralellized
+    .buffer_unordered(100)
+    // filter out the ones that aren't PRs or where we couldn't get a PR object from github
+    .filter_map(|i| ready(i.transpose()))
+}
+
+/// From the PRs shared between users if it wasn't updated since, otherwise through a conditional
+/// request, PRs are fetched again and again on every refresh. Without knowing `updated_at` we
+/// can't tell whether a shared PR is still up to date, so those are always requested.
 pub async fn get_pr(
-    octocrab: &Octocrab,
+    login_context: &LoginContext,
     repo: Repo,
     pr_number: u64,
-) -> Result<PullRequest, octocrab::Error> {
-    octocrab.pulls(&repo.owner, &repo.name).get(pr_number).await
+    updated_at: Option<Timestamp>,
+) -> color_eyre::Result<GitHubPr> {
+    let shared_prs = &login_context.state.shared_prs;
+    if let Some(updated_at) = updated_at
+        && let Some(pr) = shared_prs.get(&repo, pr_number, &updated_at).await
+    {
+        return Ok(pr);
+    }
+
+    let pr = login_context
+        .state
+        .etags
+        .get_pr(&login_context.github, &repo, pr_number)
+        .await?;
+    share_pr(shared_prs, &repo, &pr).await;
+
+    Ok(pr)
+}
+
+/// A bunch of PRs at once, for when there are too many to get one by one. When each was last
+/// updated is asked for first, which is much cheaper, so only the ones that changed since they
+/// were shared are fetched.
+pub async fn get_prs(
+    github: &GitHubClient,
+    shared_prs: &PrCache<GitHubPr>,
+    checks: &PrCache<Vec<Check>, String>,
+    repo: &Repo,
+    pr_numbers: &[u64],
+) -> color_eyre::Result<HashMap<u64, GitHubPr>> {
+    let updated_at = updated_at(github, repo, pr_numbers).await?;
+    prs_updated_at(github, shared_prs, checks, repo, updated_at).await
+}
+
+/// PRs of which we know when they were last updated. The ones shared since come from
+/// `shared_prs`, the others are fetched in batches and the checks that come along with them are
+/// cached too.
+async fn prs_updated_at(
+    github: &GitHubClient,
+    shared_prs: &PrCache<GitHubPr>,
+    checks: &PrCache<Vec<Check>, String>,
+    repo: &Repo,
+    updated_at: HashMap<u64, Timestamp>,
+) -> color_eyre::Result<HashMap<u64, GitHubPr>> {
+    let mut res = HashMap::new();
+    let mut outdated = Vec::new();
+
+    for (number, updated_at) in updated_at {
+        match shared_prs.get(repo, number, &updated_at).await {
+            Some(pr) => {
+                res.insert(number, pr);
+            }
+            None => outdated.push(number),
+        }
+    }
+
+    for (number, (pr, pr_checks)) in prs(github, repo, &outdated).await? {
+        share_pr(shared_prs, repo, &pr).await;
+        checks
+            .insert(repo, number, pr.head_sha.clone(), pr_checks)
+            .await;
+        res.insert(number, pr);
+    }
+
+    Ok(res)
+}
+
+/// Only PRs of public repos, anyone who can log in can see those
+async fn share_pr(shared_prs: &PrCache<GitHubPr>, repo: &Repo, pr: &GitHubPr) {
+    if !pr.private {
+        shared_prs
+            .insert(repo, pr.issue.number, pr.issue.updated_at, pr.clone())
+            .await;
+    }
+}
+
+/// The first and last page of comments on an issue, which is enough to find the latest
+/// bot comments without paginating through hundreds of comments on long discussions.
+pub async fn recent_comments(
+    github: &GitHubClient,
+    repo: &Repo,
+    issue_number: u64,
+) -> color_eyre::Result<Vec<Comment>> {
+    github
+        .request(async |octocrab| {
+            let first_page = octocrab
+                .issues(&repo.owner, &repo.name)
+                .list_comments(issue_number)
+                .per_page(100)
+                .send()
+                .await?;
+
+            let mut comments = first_page.items;
+
+            if first_page.last.is_some()
+                && let Some(last_page) = octocrab.get_page::<Comment>(&first_page.last).await?
+            {
+                comments.extend(last_page.items);
+            }
+
+            Ok(comments)
+        })
+        .await
 }
 
 enum IssueKind {
@@ -161,76 +811,92 @@ enum IssueKind {
 fn subscribed_issues(
     repo: Repo,
     login_context: Arc<LoginContext>,
-) -> impl StreamExt<Item = (Issue, PrSource)> {
-    read_paginated_issues(login_context.octocrab.clone(), repo, IssueKind::Subscribed)
-        .map(|i| (i, PrSource::Subscribed))
+) -> impl StreamExt<Item = color_eyre::Result<(Issue, PrSource)>> {
+    read_paginated_issues(login_context, repo, IssueKind::Subscribed, None)
+        .map(|i| i.map(|i| (i, PrSource::Subscribed)))
 }
 
 fn own_issues(
     repo: Repo,
     username: String,
+    since: Option<Timestamp>,
     login_context: Arc<LoginContext>,
-) -> impl StreamExt<Item = (Issue, PrSource)> {
-    read_paginated_issues(
-        login_context.octocrab.clone(),
-        repo,
-        IssueKind::Own(username),
-    )
-    .map(|i| (i, PrSource::Direct))
+) -> impl StreamExt<Item = color_eyre::Result<(Issue, PrSource)>> {
+    read_paginated_issues(login_context, repo, IssueKind::Own(username), since)
+        .map(|i| i.map(|i| (i, PrSource::Direct)))
 }
 
 fn assigned_issues(
     repo: Repo,
     username: String,
+    since: Option<Timestamp>,
     login_context: Arc<LoginContext>,
-) -> impl StreamExt<Item = (Issue, PrSource)> {
-    read_paginated_issues(
-        login_context.octocrab.clone(),
-        repo,
-        IssueKind::Assigned(username),
-    )
-    .map(|i| (i, PrSource::Direct))
+) -> impl StreamExt<Item = color_eyre::Result<(Issue, PrSource)>> {
+    read_paginated_issues(login_context, repo, IssueKind::Assigned(username), since)
+        .map(|i| i.map(|i| (i, PrSource::Direct)))
 }
 
+/// GitHub sometimes returns an empty first page for a bit, this is how often we ask again
+const EMPTY_PAGE_RETRIES: u32 = 3;
+
 fn read_paginated_issues(
-    octocrab: Octocrab,
+    login_context: Arc<LoginContext>,
     repo: Repo,
     issue_kind: IssueKind,
-) -> impl StreamExt<Item = Issue>
+    since: Option<Timestamp>,
+) -> impl StreamExt<Item = color_eyre::Result<Issue>>
 where
 {
-    let (mut tx, rx) = channel::<Issue>(0);
+    let (mut tx, rx) = channel::<color_eyre::Result<Issue>>(0);
 
     spawn(async move {
+        let github = &login_context.github;
         let mut ctr = 0;
         let mut initial_page = loop {
-            let list = octocrab.issues(repo.owner.clone(), repo.name.clone());
-            let list = list.list().state(params::State::Open).per_page(100);
-            let list = match &issue_kind {
-                IssueKind::Own(username) => list.creator(username),
-                IssueKind::Assigned(username) => list.assignee(username.as_str()),
-                IssueKind::Subscribed => list.filter("subscribed"),
-            };
+            let page = github
+                .request(async |octocrab| {
+                    let list = octocrab.issues(repo.owner.clone(), repo.name.clone());
+                    let list = list.list().state(params::State::Open).per_page(100);
+                    let list = match since {
+                        Some(since) => list.since(SystemTime::from(since)),
+                        None => list,
+                    };
+                    let list = match &issue_kind {
+                        IssueKind::Own(username) => list.creator(username),
+                        IssueKind::Assigned(username) => list.assignee(username.as_str()),
+                        IssueKind::Subscribed => list.filter("subscribed"),
+                    };
+
+                    list.send().await.context("list issues")
+                })
+                .await;
 
-            let page = match list.send().await {
+            let page = match page {
                 Ok(i) => i,
                 Err(e) => {
                     tracing::error!("{e}");
+                    tx.send(Err(e)).await.unwrap();
                     return;
                 }
             };
 
-            if page.total_count.is_none() && page.items.is_empty() {
-                // let rate_limit = octocrab.ratelimit().get().await.context("rate limit")?;
-                tracing::debug!("waiting...");
-                ctr += 1;
-                sleep(Duration::from_millis(50)).await;
-
-                if ctr == 20 {
-                    tracing::error!("no issues after trying 20 times");
+            // with `since` there often just is nothing new
+            if since.is_none() && page.total_count.is_none() && page.items.is_empty() {
+                if ctr == EMPTY_PAGE_RETRIES
+                    || login_context
+                        .state
+                        .rate_limits
+                        .backing_off(&login_context.base_username)
+                        .await
+                {
+                    tracing::debug!("no issues after trying {} times", ctr + 1);
                     return;
                 }
 
+                tracing::debug!("waiting...");
+                sleep(Duration::from_millis(250 * 2u64.pow(ctr))).await;
+                ctr += 1;
+
                 continue;
             }
 
@@ -240,15 +906,21 @@ where
         loop {
             let next = initial_page.next.clone();
 
-            tx.send_all(&mut stream::iter(initial_page.items).map(Result::Ok))
+            tx.send_all(&mut stream::iter(initial_page.items).map(|i| Ok(Ok(i))))
                 .await
                 .unwrap();
 
-            initial_page = match octocrab.get_page::<Issue>(&next).await {
+            let next_page = github
+                .request(async |octocrab| {
+                    octocrab.get_page::<Issue>(&next).await.context("next page")
+                })
+                .await;
+            initial_page = match next_page {
                 Ok(Some(next_page)) => next_page,
                 Ok(None) => break,
                 Err(e) => {
                     tracing::error!("error getting next page: {e}");
+                    tx.send(Err(e)).await.unwrap();
                     break;
                 }
             }
@@ -257,3 +929,86 @@ where
 
     rx
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Arc};
+
+    use jiff::Timestamp;
+    use octocrab::Octocrab;
+    use url::Url;
+
+    use super::{Fetched, PrFingerprint, previous_in_repo};
+    use crate::{
+        api::{
+            client::GitHubClient,
+            graphql::user_prs,
+            rate_limit::{RateLimits, rate_limit},
+        },
+        model::{Author, CiStatus, Pr, PrStatus, Repo},
+    };
+
+    fn repo(name: &str) -> Repo {
+        Repo {
+            owner: "rust-lang".to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    fn previous(repo: Repo, number: u64) -> (PrFingerprint, Pr) {
+        let fingerprint = PrFingerprint {
+            updated_at: Timestamp::UNIX_EPOCH,
+            head_sha: None,
+            checks: None,
+            in_bors_queue: false,
+            crater: None,
+            perf: None,
+            fcp: None,
+        };
+        let pr = Pr {
+            link: Url::parse(&format!("https://github.com/{repo}/pull/{number}")).unwrap(),
+            repo,
+            title: format!("PR #{number}"),
+            description: None,
+            number,
+            author: Author::from_username("alice".to_string()),
+            reviewers: Vec::new(),
+            status: PrStatus::Ready { try_build: None },
+            ci_status: CiStatus::Unknown,
+            checks: Vec::new(),
+            perf: None,
+            ci_failure: None,
+            broken_rollup: None,
+            stale_sources: Vec::new(),
+            scraped: true,
+            failed_at: None,
+            created: Timestamp::UNIX_EPOCH,
+        };
+        (fingerprint, pr)
+    }
+
+    #[tokio::test]
+    async fn backoff_keeps_previous_prs() {
+        let rate_limits = Arc::new(RateLimits::default());
+        let github = GitHubClient::new(
+            Octocrab::default(),
+            "alice".to_string(),
+            rate_limits.clone(),
+        );
+        rate_limits.back_off("alice", None).await;
+
+        // fails without asking GitHub, as a rate limit rather than something to fall back from
+        let error = user_prs(&github, &repo("rust"), "alice", None)
+            .await
+            .unwrap_err();
+        assert!(rate_limit(&error).is_some());
+
+        let previous = HashMap::from([
+            ((repo("rust"), 1), previous(repo("rust"), 1)),
+            ((repo("cargo"), 2), previous(repo("cargo"), 2)),
+        ]);
+        let kept = previous_in_repo(&previous, &repo("rust"));
+        assert_eq!(kept.len(), 1);
+        assert!(matches!(&kept[0], Fetched::Previous(i) if i.1.number == 1));
+    }
+}
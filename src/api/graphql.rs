@@ -0,0 +1,580 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::{Context, eyre};
+use jiff::Timestamp;
+use octocrab::map_github_error;
+use serde::{Deserialize, de::DeserializeOwned};
+use serde_json::{Value, json};
+use url::Url;
+
+use crate::{
+    api::{
+        checks::{CheckRun, CommitStatus},
+        client::GitHubClient,
+        rate_limit::RateLimited,
+    },
+    model::{Author, Check, GitHubIssue, GitHubPr, Repo},
+};
+
+/// Search results are the open PRs of one author, assignee or requested reviewer, and only their
+/// number and when they were updated, so a single page mostly
+const PAGE_SIZE: usize = 100;
+
+/// PRs asked about by number in a single query, when getting all of each PR
+const BATCH_SIZE: usize = 25;
+/// PRs asked about by number in a single query, when getting only a field or two of each PR
+const SMALL_BATCH_SIZE: usize = 100;
+
+/// Everything about a PR that we show, for any query that gets whole PRs
+const PR_FRAGMENTS: &str = r#"
+fragment pr on PullRequest {
+  number
+  title
+  body
+  url
+  createdAt
+  updatedAt
+  isDraft
+  mergeStateStatus
+  author { ...actor }
+  assignees(first: 20) { nodes { ...actor } }
+  labels(first: 50) { nodes { name } }
+  reviewRequests(first: 20) { nodes { requestedReviewer { ...actor } } }
+  headRefOid
+  repository { isPrivate }
+  commits(last: 1) {
+    nodes {
+      commit {
+        statusCheckRollup {
+          contexts(first: 100) {
+            nodes {
+              ... on CheckRun {
+                id: databaseId
+                name
+                status
+                conclusion
+                html_url: url
+                started_at: startedAt
+                completed_at: completedAt
+              }
+              ... on StatusContext {
+                state
+                context
+                target_url: targetUrl
+                created_at: createdAt
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+
+fragment actor on Actor {
+  login
+  avatarUrl
+  url
+  ... on User { databaseId }
+  ... on Bot { databaseId }
+}
+"#;
+
+const SEARCH_PRS: &str = r#"
+query($query: String!, $cursor: String, $first: Int!) {
+  search(query: $query, type: ISSUE, first: $first, after: $cursor) {
+    pageInfo { hasNextPage endCursor }
+    nodes { ... on PullRequest { number updatedAt } }
+  }
+}
+"#;
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphqlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphqlError {
+    message: String,
+    /// Like `RATE_LIMITED` or `NOT_FOUND`
+    #[serde(rename = "type")]
+    kind: Option<String>,
+}
+
+/// GraphQL reports errors next to whatever data it could still get, we treat any error as fatal
+fn into_data<T>(response: Response<T>) -> color_eyre::Result<T> {
+    // rate limit errors come in the body, with a 200
+    if response
+        .errors
+        .iter()
+        .any(|i| i.kind.as_deref() == Some("RATE_LIMITED"))
+    {
+        return Err(RateLimited { retry_after: None }.into());
+    }
+
+    if let Some(error) = response.errors.first() {
+        return Err(eyre!("graphql error: {}", error.message));
+    }
+
+    response
+        .data
+        .ok_or_else(|| eyre!("graphql response without data"))
+}
+
+async fn query<T: DeserializeOwned>(
+    github: &GitHubClient,
+    query: &str,
+    variables: Value,
+) -> color_eyre::Result<T> {
+    github
+        .request(async |octocrab| {
+            // raw, so secondary rate limits can be told apart by their headers
+            let response = octocrab
+                ._post(
+                    "/graphql",
+                    Some(&json!({ "query": query, "variables": variables })),
+                )
+                .await
+                .context("graphql request")?;
+            if let Some(rate_limited) =
+                RateLimited::from_response(response.status(), response.headers())
+            {
+                return Err(rate_limited.into());
+            }
+
+            let response = map_github_error(response)
+                .await
+                .context("graphql request")?;
+            let body = octocrab
+                .body_to_string(response)
+                .await
+                .context("graphql response body")?;
+            let response: Response<T> =
+                serde_json::from_str(&body).context("parse graphql response")?;
+
+            into_data(response)
+        })
+        .await
+}
+
+#[derive(Deserialize)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct SearchData {
+    search: Search,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Search {
+    page_info: PageInfo,
+    /// Empty objects for results that aren't PRs
+    nodes: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Actor {
+    login: String,
+    avatar_url: Url,
+    url: Url,
+    /// Mannequins don't have one
+    database_id: Option<u64>,
+}
+
+impl From<Actor> for Author {
+    fn from(actor: Actor) -> Self {
+        Author {
+            name: actor.login,
+            id: actor.database_id.unwrap_or_default(),
+            avatar_url: actor.avatar_url,
+            profile_url: actor.url,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Label {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewRequest {
+    /// Teams come back as empty objects
+    requested_reviewer: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct CommitNode {
+    commit: Commit,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Commit {
+    status_check_rollup: Option<StatusCheckRollup>,
+}
+
+#[derive(Deserialize)]
+struct StatusCheckRollup {
+    contexts: Nodes<CheckContext>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CheckContext {
+    CheckRun(CheckRun),
+    Status(CommitStatus),
+}
+
+impl From<CheckContext> for Check {
+    /// GraphQL enums are upper case, the REST API uses lower case
+    fn from(context: CheckContext) -> Self {
+        match context {
+            CheckContext::CheckRun(mut run) => {
+                run.status = run.status.to_lowercase();
+                run.conclusion = run.conclusion.map(|i| i.to_lowercase());
+                Check::from(run)
+            }
+            CheckContext::Status(mut status) => {
+                status.state = status.state.to_lowercase();
+                Check::from(status)
+            }
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PrNode {
+    number: u64,
+    title: String,
+    body: String,
+    url: Url,
+    created_at: Timestamp,
+    updated_at: Timestamp,
+    is_draft: bool,
+    merge_state_status: String,
+    /// Deleted accounts
+    author: Option<Actor>,
+    assignees: Nodes<Actor>,
+    labels: Nodes<Label>,
+    review_requests: Nodes<ReviewRequest>,
+    head_ref_oid: String,
+    repository: Repository,
+    commits: Nodes<CommitNode>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Repository {
+    is_private: bool,
+}
+
+impl PrNode {
+    fn into_parts(self) -> (GitHubPr, Vec<Check>) {
+        // REST says deleted accounts are GitHub's ghost user, GraphQL says nothing
+        let author = self
+            .author
+            .map(Author::from)
+            .unwrap_or_else(|| Author::from_username("ghost".to_string()));
+
+        let pr = GitHubPr {
+            issue: GitHubIssue {
+                number: self.number,
+                title: self.title,
+                // REST returns no body rather than an empty one
+                body: (!self.body.is_empty()).then_some(self.body),
+                url: self.url,
+                author,
+                assignees: self.assignees.nodes.into_iter().map(Author::from).collect(),
+                labels: self.labels.nodes.into_iter().map(|i| i.name).collect(),
+                created_at: self.created_at,
+                updated_at: self.updated_at,
+            },
+            head_sha: self.head_ref_oid,
+            requested_reviewers: self
+                .review_requests
+                .nodes
+                .into_iter()
+                .filter_map(|i| serde_json::from_value::<Actor>(i.requested_reviewer?).ok())
+                .map(Author::from)
+                .collect(),
+            draft: self.is_draft,
+            conflicted: matches!(self.merge_state_status.as_str(), "BEHIND" | "DIRTY"),
+            private: self.repository.is_private,
+        };
+
+        let checks = self
+            .commits
+            .nodes
+            .into_iter()
+            .filter_map(|i| i.commit.status_check_rollup)
+            .flat_map(|i| i.contexts.nodes)
+            .map(Check::from)
+            .collect();
+
+        (pr, checks)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UpdatedAt {
+    number: u64,
+    updated_at: Timestamp,
+}
+
+async fn search_prs(github: &GitHubClient, search: String) -> color_eyre::Result<Vec<UpdatedAt>> {
+    let mut res = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let data: SearchData = query(
+            github,
+            SEARCH_PRS,
+            json!({ "query": search, "cursor": cursor, "first": PAGE_SIZE }),
+        )
+        .await?;
+
+        for node in data.search.nodes {
+            // results that aren't PRs come back as empty objects
+            if node.as_object().is_some_and(|i| i.is_empty()) {
+                continue;
+            }
+            res.push(serde_json::from_value(node).context("parse PR")?);
+        }
+
+        match data.search.page_info {
+            PageInfo {
+                has_next_page: true,
+                end_cursor: Some(end_cursor),
+            } => cursor = Some(end_cursor),
+            _ => break,
+        }
+    }
+
+    Ok(res)
+}
+
+/// When each of the open PRs someone wrote, is assigned to or was asked to review in a repo was
+/// last updated, which is what listing issues does with the REST API. The PRs themselves are
+/// gotten separately, so the ones we already have don't have to be fetched again. With `since`,
+/// only the ones updated since then.
+pub async fn user_prs(
+    github: &GitHubClient,
+    repo: &Repo,
+    username: &str,
+    since: Option<Timestamp>,
+) -> color_eyre::Result<HashMap<u64, Timestamp>> {
+    let mut res = HashMap::new();
+
+    let updated = since
+        .map(|i| format!(" updated:>={}", i.strftime("%Y-%m-%dT%H:%M:%SZ")))
+        .unwrap_or_default();
+    for qualifier in ["author", "assignee", "review-requested"] {
+        let search = format!("repo:{repo} is:pr is:open {qualifier}:{username}{updated}");
+
+        for node in search_prs(github, search).await? {
+            res.insert(node.number, node.updated_at);
+        }
+    }
+
+    Ok(res)
+}
+
+#[derive(Deserialize)]
+struct RepositoryData<T> {
+    repository: HashMap<String, Option<T>>,
+}
+
+/// `fields` of a bunch of PRs by number, `batch_size` of them at a time, each under its own alias
+async fn pull_requests<T: DeserializeOwned>(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_numbers: &[u64],
+    batch_size: usize,
+    fields: &str,
+    fragments: &str,
+) -> color_eyre::Result<HashMap<u64, T>> {
+    let mut res = HashMap::new();
+
+    for batch in pr_numbers.chunks(batch_size) {
+        let aliases = batch
+            .iter()
+            .map(|i| format!("pr{i}: pullRequest(number: {i}) {{ {fields} }}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let batch_query = format!(
+            "query($owner: String!, $name: String!) {{ repository(owner: $owner, name: $name) {{ {aliases} }} }}\n{fragments}"
+        );
+
+        let data: RepositoryData<T> = query(
+            github,
+            &batch_query,
+            json!({ "owner": repo.owner, "name": repo.name }),
+        )
+        .await?;
+
+        res.extend(
+            data.repository
+                .into_iter()
+                .filter_map(|(alias, pr)| Some((alias.strip_prefix("pr")?.parse().ok()?, pr?))),
+        );
+    }
+
+    Ok(res)
+}
+
+/// A bunch of PRs of a repo, with the checks on their head commit
+pub async fn prs(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_numbers: &[u64],
+) -> color_eyre::Result<HashMap<u64, (GitHubPr, Vec<Check>)>> {
+    let nodes: HashMap<u64, PrNode> =
+        pull_requests(github, repo, pr_numbers, BATCH_SIZE, "...pr", PR_FRAGMENTS).await?;
+
+    Ok(nodes
+        .into_iter()
+        .map(|(number, node)| (number, node.into_parts()))
+        .collect())
+}
+
+/// When each of a bunch of PRs was last updated, which is all it takes to tell whether a PR we
+/// already have is still up to date
+pub async fn updated_at(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_numbers: &[u64],
+) -> color_eyre::Result<HashMap<u64, Timestamp>> {
+    let nodes: HashMap<u64, UpdatedAt> = pull_requests(
+        github,
+        repo,
+        pr_numbers,
+        SMALL_BATCH_SIZE,
+        "number updatedAt",
+        "",
+    )
+    .await?;
+
+    Ok(nodes
+        .into_iter()
+        .map(|(number, i)| (number, i.updated_at))
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct CommentAuthor {
+    login: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Comment {
+    /// Deleted accounts
+    author: Option<CommentAuthor>,
+    pub body: String,
+    pub created_at: Timestamp,
+}
+
+impl Comment {
+    /// Bots don't have the `[bot]` suffix they have in the REST API
+    pub fn author(&self) -> Option<&str> {
+        self.author.as_ref().map(|i| i.login.as_str())
+    }
+}
+
+#[derive(Deserialize)]
+struct Comments {
+    comments: Nodes<Comment>,
+}
+
+/// The last few comments on each of a bunch of PRs, oldest first
+pub async fn last_comments(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_numbers: &[u64],
+) -> color_eyre::Result<HashMap<u64, Vec<Comment>>> {
+    let nodes: HashMap<u64, Comments> = pull_requests(
+        github,
+        repo,
+        pr_numbers,
+        SMALL_BATCH_SIZE,
+        "comments(last: 10) { nodes { author { login } body createdAt } }",
+        "",
+    )
+    .await?;
+
+    Ok(nodes
+        .into_iter()
+        .map(|(number, i)| (number, i.comments.nodes))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::PrNode;
+
+    fn node(author: serde_json::Value) -> PrNode {
+        serde_json::from_value(json!({
+            "number": 1234,
+            "title": "Fix everything",
+            "body": "",
+            "url": "https://github.com/rust-lang/rust/pull/1234",
+            "createdAt": "2025-01-01T00:00:00Z",
+            "updatedAt": "2025-01-02T00:00:00Z",
+            "isDraft": false,
+            "mergeStateStatus": "CLEAN",
+            "author": author,
+            "assignees": { "nodes": [] },
+            "labels": { "nodes": [{ "name": "S-waiting-on-review" }] },
+            "reviewRequests": { "nodes": [{ "requestedReviewer": {} }] },
+            "headRefOid": "abc123",
+            "repository": { "isPrivate": false },
+            "commits": { "nodes": [{ "commit": { "statusCheckRollup": null } }] },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn author() {
+        let (pr, checks) = node(json!({
+            "login": "alice",
+            "avatarUrl": "https://avatars.githubusercontent.com/u/1",
+            "url": "https://github.com/alice",
+            "databaseId": 1,
+        }))
+        .into_parts();
+
+        assert_eq!(pr.issue.author.name, "alice");
+        assert_eq!(pr.issue.author.id, 1);
+        assert_eq!(pr.issue.body, None);
+        assert_eq!(pr.issue.labels, ["S-waiting-on-review"]);
+        assert!(pr.requested_reviewers.is_empty());
+        assert!(checks.is_empty());
+    }
+
+    #[test]
+    fn deleted_author_is_ghost() {
+        let (pr, _) = node(serde_json::Value::Null).into_parts();
+        assert_eq!(pr.issue.author.name, "ghost");
+        assert_eq!(pr.issue.number, 1234);
+    }
+}
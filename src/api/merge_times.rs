@@ -0,0 +1,240 @@
+use std::collections::VecDeque;
+
+use jiff::{SignedDuration, Timestamp};
+
+use crate::api::{
+    bors::{BorsQueue, BorsStatus},
+    rollup::RollupQueue,
+};
+
+/// How many finished runs estimates are based on
+const HISTORY: usize = 50;
+
+/// About twice as long as the bors queue takes to reload. The queue is only reloaded when someone
+/// looks at it, and after a longer gap we can't tell when in between a run started or ended.
+const MAX_GAP: SignedDuration = SignedDuration::from_mins(2);
+
+struct RunningEntry {
+    pr_number: u64,
+    since: Timestamp,
+    /// False if it was already running when we first looked, so we don't know when it started
+    saw_start: bool,
+}
+
+/// How long bors test runs take in a repo, learned by watching which PR is running over time
+#[derive(Default)]
+pub struct MergeTimes {
+    last_observed: Option<Timestamp>,
+    running: Option<RunningEntry>,
+    durations: VecDeque<SignedDuration>,
+    /// Whether each finished run ended in a merge
+    outcomes: VecDeque<bool>,
+}
+
+fn push_bounded<T>(history: &mut VecDeque<T>, value: T) {
+    if history.len() == HISTORY {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+impl MergeTimes {
+    /// Call with every fresh copy of the queue
+    pub fn observe(&mut self, queue: &BorsQueue, now: Timestamp) {
+        let running = queue
+            .items
+            .iter()
+            .find(|i| i.running && i.status == BorsStatus::Pending)
+            .map(|i| i.pr_number);
+
+        // whether we saw the queue right before this, so we know roughly when things changed
+        let continuous = self
+            .last_observed
+            .is_some_and(|i| now.duration_since(i) <= MAX_GAP);
+        self.last_observed = Some(now);
+
+        if let (Some(previous), Some(running)) = (&self.running, running)
+            && previous.pr_number == running
+        {
+            return;
+        }
+
+        if let Some(previous) = self.running.take() {
+            if previous.saw_start && continuous {
+                push_bounded(&mut self.durations, now.duration_since(previous.since));
+            }
+
+            // a PR that left the queue got merged, one that's still there might've just been retried
+            let merged = match queue.for_pr(previous.pr_number).map(|i| &i.status) {
+                None | Some(BorsStatus::Success) => Some(true),
+                Some(BorsStatus::Failure | BorsStatus::Error) => Some(false),
+                Some(_) => None,
+            };
+            if let Some(merged) = merged {
+                push_bounded(&mut self.outcomes, merged);
+            }
+        }
+
+        self.running = running.map(|pr_number| RunningEntry {
+            pr_number,
+            since: now,
+            saw_start: continuous,
+        });
+    }
+
+    /// The median duration of a test run
+    pub fn p50(&self) -> Option<SignedDuration> {
+        let mut durations = self.durations.iter().copied().collect::<Vec<_>>();
+        durations.sort();
+        durations.get(durations.len() / 2).copied()
+    }
+
+    /// Smoothed, so a few observations don't claim everything always fails
+    fn success_rate(&self) -> f64 {
+        let successes = self.outcomes.iter().filter(|i| **i).count();
+        (successes + 1) as f64 / (self.outcomes.len() + 2) as f64
+    }
+
+    /// How long until a PR with `runs_ahead` test runs in front of it is merged. Failed runs
+    /// have to be done again, so this is more than the median run times the runs ahead.
+    pub fn eta(&self, runs_ahead: usize, now: Timestamp) -> Option<SignedDuration> {
+        let per_run = self.p50()?;
+        // every failure costs another run before the next PR can be tested
+        let per_merge = per_run.div_f64(self.success_rate());
+
+        let elapsed = self
+            .running
+            .as_ref()
+            .map(|i| now.duration_since(i.since).min(per_run))
+            .unwrap_or_default();
+
+        Some((per_merge * (runs_ahead as i32 + 1) - elapsed).max(SignedDuration::ZERO))
+    }
+}
+
+/// Test runs ahead of the entry at `position`. Rolled up PRs are merged as part of their
+/// rollup, so only the rollup counts.
+pub fn runs_ahead(queue: &BorsQueue, rollup_queue: &RollupQueue, position: usize) -> usize {
+    queue
+        .items
+        .iter()
+        .filter(|i| i.position_in_queue < position)
+        .filter(|i| !i.try_build && matches!(i.status, BorsStatus::Approved | BorsStatus::Pending))
+        .filter(|i| {
+            !rollup_queue
+                .rollups
+                .iter()
+                .any(|r| r.is_pending() && r.pr_numbers.contains(&i.pr_number))
+        })
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use jiff::{SignedDuration, Timestamp};
+
+    use super::MergeTimes;
+    use crate::api::bors::{BorsPr, BorsQueue, BorsStatus};
+
+    fn pr(pr_number: u64, status: BorsStatus, running: bool) -> BorsPr {
+        BorsPr {
+            status,
+            running,
+            ..BorsPr::test(pr_number)
+        }
+    }
+
+    /// `running` is being tested, the rest are approved
+    fn queue(running: Option<u64>, waiting: &[u64]) -> BorsQueue {
+        BorsQueue::test(
+            running
+                .map(|i| pr(i, BorsStatus::Pending, true))
+                .into_iter()
+                .chain(waiting.iter().map(|i| pr(*i, BorsStatus::Approved, false)))
+                .collect(),
+        )
+    }
+
+    fn at(minutes: i64) -> Timestamp {
+        Timestamp::UNIX_EPOCH + SignedDuration::from_mins(minutes)
+    }
+
+    /// Watches the queue every minute, with a run of every PR taking `minutes`
+    fn watch_runs(times: &mut MergeTimes, prs: &[u64], minutes: i64) -> i64 {
+        let mut now = 0;
+        for (idx, pr) in prs.iter().enumerate() {
+            for _ in 0..minutes {
+                times.observe(&queue(Some(*pr), &prs[idx + 1..]), at(now));
+                now += 1;
+            }
+        }
+        times.observe(&queue(None, &[]), at(now));
+        now
+    }
+
+    #[test]
+    fn run_already_going_is_not_counted() {
+        let mut times = MergeTimes::default();
+        watch_runs(&mut times, &[1, 2, 3], 30);
+
+        // the run of #1 started before we first looked
+        assert_eq!(times.durations.len(), 2);
+        assert_eq!(times.p50(), Some(SignedDuration::from_mins(30)));
+        assert_eq!(times.outcomes.len(), 3);
+    }
+
+    #[test]
+    fn gap_between_observations_is_not_a_run() {
+        let mut times = MergeTimes::default();
+        times.observe(&queue(Some(1), &[2]), at(0));
+        times.observe(&queue(Some(2), &[]), at(1));
+        // nobody looked for hours
+        times.observe(&queue(None, &[]), at(300));
+
+        assert!(times.durations.is_empty());
+        assert_eq!(times.p50(), None);
+
+        // and a run starting during the gap has no known start either
+        times.observe(&queue(Some(3), &[]), at(600));
+        times.observe(&queue(None, &[]), at(601));
+        assert!(times.durations.is_empty());
+    }
+
+    #[test]
+    fn failed_runs_are_outcomes() {
+        let mut times = MergeTimes::default();
+        times.observe(&queue(Some(1), &[]), at(0));
+        times.observe(
+            &BorsQueue::test(vec![pr(1, BorsStatus::Failure, false)]),
+            at(1),
+        );
+
+        assert_eq!(times.outcomes, [false]);
+    }
+
+    #[test]
+    fn eta_counts_runs_ahead_and_failures() {
+        let mut times = MergeTimes::default();
+        let now = watch_runs(&mut times, &[1, 2, 3], 60);
+        assert_eq!(times.p50(), Some(SignedDuration::from_hours(1)));
+
+        // 3 merges out of 3 smoothed to 4 out of 5, so every merge takes 1h15
+        assert_eq!(times.eta(0, at(now)), Some(SignedDuration::from_mins(75)));
+        assert_eq!(times.eta(3, at(now)), Some(SignedDuration::from_mins(300)));
+
+        // the run that's going counts as partly done
+        times.observe(&queue(Some(4), &[]), at(now));
+        assert_eq!(
+            times.eta(0, at(now + 30)),
+            Some(SignedDuration::from_mins(45))
+        );
+    }
+
+    #[test]
+    fn no_eta_without_runs() {
+        let mut times = MergeTimes::default();
+        times.observe(&queue(Some(1), &[2]), at(0));
+
+        assert_eq!(times.eta(1, at(10)), None);
+    }
+}
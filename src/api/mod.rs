@@ -1,68 +1,307 @@
-use std::{pin::Pin, sync::Arc, time::Duration};
+use std::{collections::HashMap, pin::Pin, sync::Arc, time::Duration};
 
-use tokio::{
-    sync::{Mutex, MutexGuard},
-    time::Instant,
-};
+use jiff::Timestamp;
+use tokio::{spawn, sync::Mutex, time::Instant};
 
 pub mod bors;
+pub mod checks;
+pub mod ci_failure;
+pub mod client;
 pub mod crater;
+pub mod etags;
 pub mod github;
+pub mod graphql;
+pub mod merge_times;
+pub mod perf;
+pub mod rate_limit;
+pub mod repos;
 pub mod rfcbot;
 pub mod rollup;
+pub mod rollup_failure;
+pub mod scrape_health;
+pub mod spurious;
+pub mod try_build;
 
-pub struct Cache<'a, T: 'a, P = ()> {
-    f: Box<dyn 'a + Send + Sync + Fn(P) -> Pin<Box<dyn 'a + Send + Future<Output = T>>>>,
-    last_value: Mutex<(Option<Arc<T>>, Instant)>,
+use crate::model::Repo;
+
+type Reload<T, P> =
+    dyn Send + Sync + Fn(P) -> Pin<Box<dyn Send + Future<Output = color_eyre::Result<T>>>>;
+
+struct CacheState<T> {
+    /// The last value that loaded successfully, and when
+    value: Option<(Arc<T>, Instant)>,
+    last_attempt: Option<Instant>,
+    /// Of the last reload, cleared once one succeeds again
+    last_error: Option<String>,
+}
+
+struct CacheInner<T, P> {
+    f: Box<Reload<T, P>>,
+    state: Mutex<CacheState<T>>,
+    /// Held by whoever is reloading, so there's only ever one reload at a time
+    reloading: Arc<Mutex<()>>,
     period: Duration,
 }
 
-impl<'a, T: 'a, P: Send> Cache<'a, T, P> {
-    pub async fn get_with_param(&self, p: P) -> Arc<T> {
-        let guard = self.last_value.lock().await;
-        if let (Some(v), t) = &*guard {
-            if Instant::now().duration_since(*t) > self.period {
-                self.reload(guard, p).await
-            } else {
-                v.clone()
+impl<T, P> CacheInner<T, P> {
+    fn due(&self, state: &CacheState<T>) -> bool {
+        state
+            .last_attempt
+            .is_none_or(|t| Instant::now().duration_since(t) > self.period)
+    }
+
+    async fn reload(&self, p: P) {
+        let result = (self.f)(p).await;
+        let now = Instant::now();
+
+        let mut state = self.state.lock().await;
+        state.last_attempt = Some(now);
+        match result {
+            Ok(value) => {
+                state.value = Some((Arc::new(value), now));
+                state.last_error = None;
             }
-        } else {
-            self.reload(guard, p).await
+            // keep serving the last good value
+            Err(e) => state.last_error = Some(format!("{e:#}")),
         }
     }
+}
+
+/// How fresh the value of a [`Cache`] is
+#[derive(Clone, Debug)]
+pub struct CacheStatus {
+    /// None if it never loaded successfully
+    pub age: Option<Duration>,
+    pub last_error: Option<String>,
+}
+
+/// A value that's reloaded every `period`. While it reloads, in the background, everyone gets
+/// the last good value, only the very first load is waited for.
+pub struct Cache<T, P = ()>(Arc<CacheInner<T, P>>);
+
+impl<T, P> Clone for Cache<T, P> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Default + Send + Sync + 'static, P: Send + 'static> Cache<T, P> {
+    pub async fn get_with_param(&self, p: P) -> Arc<T> {
+        let (value, due) = {
+            let state = self.0.state.lock().await;
+            (
+                state.value.as_ref().map(|(v, _)| v.clone()),
+                self.0.due(&state),
+            )
+        };
+
+        match value {
+            Some(v) => {
+                if due && let Ok(guard) = self.0.reloading.clone().try_lock_owned() {
+                    let inner = self.0.clone();
+                    spawn(async move {
+                        inner.reload(p).await;
+                        drop(guard);
+                    });
+                }
+                v
+            }
+            None => {
+                if due {
+                    let _guard = self.0.reloading.lock().await;
+                    // maybe it loaded, or failed to, while we were waiting. If it failed, everyone
+                    // who was waiting gets the default rather than trying again one after another
+                    let still_due = {
+                        let state = self.0.state.lock().await;
+                        state.value.is_none() && self.0.due(&state)
+                    };
+                    if still_due {
+                        self.0.reload(p).await;
+                    }
+                }
 
-    async fn reload(&self, mut g: MutexGuard<'_, (Option<Arc<T>>, Instant)>, p: P) -> Arc<T> {
-        let new_value = Arc::new((self.f)(p).await);
-        *g = (Some(new_value.clone()), Instant::now());
+                self.0
+                    .state
+                    .lock()
+                    .await
+                    .value
+                    .as_ref()
+                    .map(|(v, _)| v.clone())
+                    .unwrap_or_default()
+            }
+        }
+    }
 
-        new_value
+    pub async fn status(&self) -> CacheStatus {
+        let state = self.0.state.lock().await;
+        CacheStatus {
+            age: state.value.as_ref().map(|(_, t)| t.elapsed()),
+            last_error: state.last_error.clone(),
+        }
     }
 
-    pub fn new_with_param<F: Future<Output = T> + Send + 'a>(
-        f: impl 'a + Send + Sync + Fn(P) -> F,
+    pub fn new_with_param<F: Future<Output = color_eyre::Result<T>> + Send + 'static>(
+        f: impl 'static + Send + Sync + Fn(P) -> F,
         period: Duration,
     ) -> Self {
-        Self {
+        Self(Arc::new(CacheInner {
             f: Box::new(move |p| Box::pin(f(p))),
-            last_value: Mutex::new((None, Instant::now())),
+            state: Mutex::new(CacheState {
+                value: None,
+                last_attempt: None,
+                last_error: None,
+            }),
+            reloading: Arc::new(Mutex::new(())),
             period,
-        }
+        }))
     }
 }
 
-impl<'a, T: 'a> Cache<'a, T> {
-    pub fn new<F: Future<Output = T> + Send + 'a>(
-        f: impl 'a + Send + Sync + Fn() -> F,
+impl<T: Default + Send + Sync + 'static> Cache<T> {
+    pub fn new<F: Future<Output = color_eyre::Result<T>> + Send + 'static>(
+        f: impl 'static + Send + Sync + Fn() -> F,
         period: Duration,
     ) -> Self {
-        Self {
-            f: Box::new(move |()| Box::pin(f())),
-            last_value: Mutex::new((None, Instant::now())),
-            period,
-        }
+        Self::new_with_param(move |()| f(), period)
     }
 
     pub async fn get(&self) -> Arc<T> {
         self.get_with_param(()).await
     }
 }
+
+/// Values nobody asked for in this long are dropped, like those of PRs that were closed
+const PR_CACHE_UNUSED_FOR: Duration = Duration::from_secs(24 * 60 * 60);
+
+struct PrCacheEntry<T, V> {
+    version: V,
+    value: T,
+    used: Instant,
+}
+
+struct PrCacheState<T, V> {
+    entries: HashMap<(Repo, u64), PrCacheEntry<T, V>>,
+    /// When unused entries were last dropped
+    swept: Instant,
+}
+
+/// Values derived from a single PR, valid for as long as its `version` stays the same. That's the
+/// PR's `updated_at` by default, or e.g. its head commit for things that only change with pushes.
+pub struct PrCache<T, V = Timestamp> {
+    state: Mutex<PrCacheState<T, V>>,
+}
+
+impl<T: Clone, V: PartialEq> PrCache<T, V> {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(PrCacheState {
+                entries: HashMap::new(),
+                swept: Instant::now(),
+            }),
+        }
+    }
+
+    pub async fn get(&self, repo: &Repo, number: u64, version: &V) -> Option<T> {
+        let mut state = self.state.lock().await;
+        let entry = state.entries.get_mut(&(repo.clone(), number))?;
+        if entry.version != *version {
+            return None;
+        }
+
+        entry.used = Instant::now();
+        Some(entry.value.clone())
+    }
+
+    pub async fn get_or_reload(
+        &self,
+        repo: &Repo,
+        number: u64,
+        version: V,
+        reload: impl Future<Output = color_eyre::Result<T>>,
+    ) -> color_eyre::Result<T> {
+        if let Some(value) = self.get(repo, number, &version).await {
+            return Ok(value);
+        }
+
+        // don't hold the lock while reloading, other PRs shouldn't wait on this one
+        let value = reload.await?;
+        self.insert(repo, number, version, value.clone()).await;
+
+        Ok(value)
+    }
+
+    /// For values that came along with something else, like checks with a batch of PRs
+    pub async fn insert(&self, repo: &Repo, number: u64, version: V, value: T) {
+        let mut state = self.state.lock().await;
+
+        // sweeping once in a while is plenty, entries only need to go eventually
+        if state.swept.elapsed() > PR_CACHE_UNUSED_FOR / 24 {
+            state
+                .entries
+                .retain(|_, i| i.used.elapsed() < PR_CACHE_UNUSED_FOR);
+            state.swept = Instant::now();
+        }
+
+        state.entries.insert(
+            (repo.clone(), number),
+            PrCacheEntry {
+                version,
+                value,
+                used: Instant::now(),
+            },
+        );
+    }
+
+    /// Forget a value that may still change without its version changing
+    pub async fn remove(&self, repo: &Repo, number: u64) {
+        self.state
+            .lock()
+            .await
+            .entries
+            .remove(&(repo.clone(), number));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            Arc,
+            atomic::{AtomicUsize, Ordering},
+        },
+        time::Duration,
+    };
+
+    use color_eyre::eyre::eyre;
+    use tokio::time::sleep;
+
+    use super::Cache;
+
+    #[tokio::test]
+    async fn failed_first_load_is_not_retried_by_waiting_callers() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let cache = Cache::new(
+            {
+                let calls = calls.clone();
+                move || {
+                    let calls = calls.clone();
+                    async move {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        sleep(Duration::from_millis(10)).await;
+                        Err::<Vec<u64>, _>(eyre!("bors is down"))
+                    }
+                }
+            },
+            Duration::from_secs(60),
+        );
+
+        let (a, b, c) = tokio::join!(cache.get(), cache.get(), cache.get());
+
+        assert!(a.is_empty() && b.is_empty() && c.is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            cache.status().await.last_error.as_deref(),
+            Some("bors is down")
+        );
+    }
+}
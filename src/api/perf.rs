@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::Context;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    api::{client::GitHubClient, github::recent_comments},
+    model::{PerfChanges, PerfStatus, PerfSummary, PerfVerdict, Repo},
+};
+
+/// The data behind https://perf.rust-lang.org/status.html, which itself is rendered client side
+const URL: &str = "https://perf.rust-lang.org/perf/status_page";
+
+const RUST_TIMER: &str = "rust-timer";
+
+#[derive(Deserialize)]
+pub enum MissingReason {
+    Master {
+        pr: u64,
+    },
+    Try {
+        pr: u64,
+    },
+    /// The commit that's currently being benchmarked
+    InProgress(Option<Box<MissingReason>>),
+}
+
+impl MissingReason {
+    fn pr(&self) -> Option<u64> {
+        match self {
+            MissingReason::Master { pr } | MissingReason::Try { pr } => Some(*pr),
+            MissingReason::InProgress(reason) => reason.as_ref()?.pr(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct StatusPage {
+    /// The benchmarking queue, in order
+    pub missing: Vec<(serde_json::Value, MissingReason)>,
+}
+
+pub async fn get_perf_queue() -> color_eyre::Result<HashMap<u64, PerfStatus>> {
+    let response = reqwest::get(URL).await?;
+    let body: StatusPage = response.json().await.context("body")?;
+
+    let mut results = HashMap::new();
+    let mut number_in_queue = 0;
+
+    for (_commit, reason) in body.missing {
+        let Some(pr) = reason.pr() else {
+            continue;
+        };
+
+        let status = match reason {
+            MissingReason::InProgress(_) => PerfStatus::Benchmarking,
+            _ => {
+                number_in_queue += 1;
+                PerfStatus::Queued {
+                    num_before: number_in_queue - 1,
+                }
+            }
+        };
+
+        // a PR can be in the queue multiple times, only keep its first entry
+        results.entry(pr).or_insert(status);
+    }
+
+    Ok(results)
+}
+
+/// Parses `[0.2%, 0.9%]`
+fn parse_range(cell: &str) -> Option<(f64, f64)> {
+    let (low, high) = cell
+        .trim()
+        .strip_prefix('[')?
+        .strip_suffix(']')?
+        .split_once(',')?;
+    let low = low.trim().trim_end_matches('%').parse().ok()?;
+    let high = high.trim().trim_end_matches('%').parse().ok()?;
+    Some((low, high))
+}
+
+/// Adds a row of a rust-timer summary table, like
+/// `| Regressions ❌ <br /> (primary) | 0.4% | [0.2%, 0.9%] | 12 |`
+fn add_row(changes: &mut PerfChanges, cells: &[&str]) {
+    let [_, _mean, range, count] = cells else {
+        tracing::warn!("weird rust-timer table row: {cells:?}");
+        return;
+    };
+
+    changes.count += count.trim().parse::<usize>().unwrap_or(0);
+
+    if let Some((low, high)) = parse_range(range) {
+        changes.range = Some(match changes.range {
+            Some((old_low, old_high)) => (old_low.min(low), old_high.max(high)),
+            None => (low, high),
+        });
+    }
+}
+
+/// Parses one `### [Metric](...)` section of a rust-timer comment
+fn parse_metric(section: &str) -> (PerfChanges, PerfChanges) {
+    let mut regressions = PerfChanges::default();
+    let mut improvements = PerfChanges::default();
+
+    for line in section.lines() {
+        let cells = line.trim().trim_matches('|').split('|').collect::<Vec<_>>();
+
+        match cells.first().map(|i| i.trim()) {
+            Some(c) if c.starts_with("Regressions") => add_row(&mut regressions, &cells),
+            Some(c) if c.starts_with("Improvements") => add_row(&mut improvements, &cells),
+            _ => {}
+        }
+    }
+
+    (regressions, improvements)
+}
+
+fn verdict(regressions: &PerfChanges, improvements: &PerfChanges) -> PerfVerdict {
+    match (regressions.count > 0, improvements.count > 0) {
+        (true, true) => PerfVerdict::Mixed,
+        (true, false) => PerfVerdict::Regressions,
+        (false, true) => PerfVerdict::Improvements,
+        (false, false) => PerfVerdict::Neutral,
+    }
+}
+
+/// Parses a "Finished benchmarking commit" comment by rust-timer
+pub fn parse_perf_summary(body: &str) -> Option<PerfSummary> {
+    let (_, rest) = body.split_once("[comparison URL](")?;
+    let (url, _) = rest.split_once(')')?;
+    let comparison_url = Url::parse(url).ok()?;
+
+    let mut res = PerfSummary {
+        comparison_url,
+        regressions: PerfChanges::default(),
+        improvements: PerfChanges::default(),
+        instructions: PerfVerdict::Neutral,
+        cycles: PerfVerdict::Neutral,
+        max_rss: PerfVerdict::Neutral,
+        needs_triage: false,
+    };
+
+    for section in body.split("### ").skip(1) {
+        let (regressions, improvements) = parse_metric(section);
+        let verdict = verdict(&regressions, &improvements);
+
+        if section.starts_with("[Instruction count]") {
+            res.instructions = verdict;
+            res.regressions = regressions;
+            res.improvements = improvements;
+        } else if section.starts_with("[Cycles]") {
+            res.cycles = verdict;
+        } else if section.starts_with("[Max RSS") {
+            res.max_rss = verdict;
+        }
+    }
+
+    Some(res)
+}
+
+/// The summary in the latest "Finished benchmarking" comment by rust-timer
+pub async fn get_perf_summary(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_number: u64,
+) -> color_eyre::Result<Option<PerfSummary>> {
+    let comments = recent_comments(github, repo, pr_number)
+        .await
+        .context("get comments")?;
+
+    Ok(comments
+        .iter()
+        .rev()
+        .filter(|c| c.user.login == RUST_TIMER)
+        .filter_map(|c| c.body.as_deref())
+        .filter(|body| body.contains("Finished benchmarking commit"))
+        .find_map(parse_perf_summary))
+}
@@ -0,0 +1,283 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use color_eyre::eyre::Context;
+use jiff::{SignedDuration, Timestamp};
+use reqwest::{StatusCode, header::HeaderMap};
+use tokio::sync::{Mutex, Semaphore};
+
+use crate::api::client::GitHubClient;
+
+/// The first wait after GitHub told us to slow down, doubled every time it happens again
+const MIN_BACKOFF: SignedDuration = SignedDuration::from_mins(1);
+const MAX_BACKOFF: SignedDuration = SignedDuration::from_hours(1);
+
+/// GitHub's secondary rate limits kick in with too many concurrent requests
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// How often we ask how much is left, however many sessions a user has open
+const POLL_INTERVAL: SignedDuration = SignedDuration::from_secs(30);
+
+/// GitHub refused a request because of a (secondary) rate limit or abuse detection
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimited {
+    /// When GitHub told us how long to wait
+    pub retry_after: Option<SignedDuration>,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.retry_after {
+            Some(retry_after) => write!(f, "rate limited, retry after {retry_after:#}"),
+            None => write!(f, "rate limited"),
+        }
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+impl RateLimited {
+    /// For raw responses, before octocrab turns them into errors without their headers. GitHub
+    /// sends a `retry-after` for secondary rate limits, and no requests left for primary ones.
+    pub fn from_response(status: StatusCode, headers: &HeaderMap) -> Option<Self> {
+        if !matches!(status.as_u16(), 403 | 429) {
+            return None;
+        }
+
+        let header = |name: &str| -> Option<i64> { headers.get(name)?.to_str().ok()?.parse().ok() };
+        if let Some(seconds) = header("retry-after") {
+            return Some(Self {
+                retry_after: Some(SignedDuration::from_secs(seconds)),
+            });
+        }
+        if header("x-ratelimit-remaining") == Some(0) {
+            let retry_after = header("x-ratelimit-reset")
+                .and_then(|i| Timestamp::from_second(i).ok())
+                .map(|reset| reset.duration_since(Timestamp::now()));
+            return Some(Self { retry_after });
+        }
+
+        // without either header a 429 is still a rate limit, a 403 is just forbidden
+        (status == StatusCode::TOO_MANY_REQUESTS).then_some(Self { retry_after: None })
+    }
+}
+
+/// Whether GitHub refused a request because of a rate limit. That's either a [`RateLimited`]
+/// from a raw response or GraphQL, or an error octocrab made out of a 403 or 429.
+pub fn rate_limit(error: &color_eyre::Report) -> Option<RateLimited> {
+    error.chain().find_map(|cause| {
+        if let Some(rate_limited) = cause.downcast_ref::<RateLimited>() {
+            return Some(*rate_limited);
+        }
+
+        // octocrab drops the headers, so all that's left is what GitHub said
+        let Some(octocrab::Error::GitHub { source, .. }) = cause.downcast_ref::<octocrab::Error>()
+        else {
+            return None;
+        };
+        let message = source.message.to_lowercase();
+        let rate_limited = match source.status_code.as_u16() {
+            429 => true,
+            403 => message.contains("rate limit") || message.contains("abuse"),
+            _ => false,
+        };
+        rate_limited.then_some(RateLimited { retry_after: None })
+    })
+}
+
+#[derive(Debug)]
+struct Budget {
+    /// Of whichever API has the least left
+    fraction_left: Option<f64>,
+    reset: Option<Timestamp>,
+    /// When we last asked GitHub about the above
+    polled: Option<Timestamp>,
+    /// Until when we're backing off, and how long that backoff was
+    backoff: Option<(Timestamp, SignedDuration)>,
+    /// For requests in flight
+    permits: Arc<Semaphore>,
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self {
+            fraction_left: None,
+            reset: None,
+            polled: None,
+            backoff: None,
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS)),
+        }
+    }
+}
+
+/// When to refresh next, and whether that's later than usual because of rate limits
+pub struct NextRefresh {
+    pub at: Timestamp,
+    pub rate_limited: bool,
+}
+
+/// How much of their GitHub API budget each user has left, shared by all their sessions
+#[derive(Debug, Default)]
+pub struct RateLimits(Mutex<HashMap<String, Budget>>);
+
+impl RateLimits {
+    /// Call when GitHub refused a request, refreshes stop for a while, at least as long as GitHub
+    /// asked
+    pub async fn back_off(&self, username: &str, retry_after: Option<SignedDuration>) {
+        let now = Timestamp::now();
+        let mut budgets = self.0.lock().await;
+        let budget = budgets.entry(username.to_string()).or_default();
+
+        // it's the same episode if the previous backoff ended only recently
+        let wait = match budget.backoff {
+            Some((until, wait)) if now.duration_since(until) < wait * 2 => {
+                (wait * 2).min(MAX_BACKOFF)
+            }
+            _ => MIN_BACKOFF,
+        };
+        let wait = retry_after.map_or(wait, |i| wait.max(i));
+
+        tracing::warn!("rate limited for {username}, backing off for {wait:#}");
+        budget.backoff = Some((now + wait, wait));
+    }
+
+    pub async fn backing_off(&self, username: &str) -> bool {
+        self.backoff_left(username).await.is_some()
+    }
+
+    /// How much longer the current backoff lasts, if there is one
+    pub async fn backoff_left(&self, username: &str) -> Option<SignedDuration> {
+        let now = Timestamp::now();
+        self.0
+            .lock()
+            .await
+            .get(username)
+            .and_then(|i| i.backoff)
+            .filter(|(until, _)| *until > now)
+            .map(|(until, _)| until.duration_since(now))
+    }
+
+    pub async fn permits(&self, username: &str) -> Arc<Semaphore> {
+        let mut budgets = self.0.lock().await;
+        budgets
+            .entry(username.to_string())
+            .or_default()
+            .permits
+            .clone()
+    }
+
+    /// Whether nobody asked GitHub about this user's budget recently, and now someone will
+    async fn should_poll(&self, username: &str) -> bool {
+        let now = Timestamp::now();
+        let mut budgets = self.0.lock().await;
+        let budget = budgets.entry(username.to_string()).or_default();
+
+        if budget
+            .polled
+            .is_some_and(|polled| now.duration_since(polled) < POLL_INTERVAL)
+        {
+            return false;
+        }
+        budget.polled = Some(now);
+        true
+    }
+
+    /// Asks GitHub what's left of the budget, which itself doesn't count against it
+    async fn update(&self, github: &GitHubClient) {
+        let limits = github
+            .request(async |octocrab| octocrab.ratelimit().get().await.context("get rate limit"))
+            .await;
+        let limits = match limits {
+            Ok(i) => i,
+            Err(e) => {
+                tracing::error!("error getting rate limit: {e}");
+                return;
+            }
+        };
+
+        let lowest = [Some(limits.resources.core), limits.resources.graphql]
+            .into_iter()
+            .flatten()
+            .filter(|i| i.limit > 0)
+            .map(|i| (i.remaining as f64 / i.limit as f64, i.reset))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b));
+
+        let mut budgets = self.0.lock().await;
+        let budget = budgets.entry(github.username().to_string()).or_default();
+        budget.fraction_left = lowest.map(|(fraction, _)| fraction);
+        budget.reset = lowest.and_then(|(_, reset)| Timestamp::from_second(reset as i64).ok());
+    }
+
+    /// Normally after `refresh_rate`, but spread out as the budget runs low so it lasts until it
+    /// resets, and never during a backoff
+    pub async fn next_refresh(
+        &self,
+        github: &GitHubClient,
+        refresh_rate: SignedDuration,
+    ) -> NextRefresh {
+        let username = github.username();
+        if !self.backing_off(username).await && self.should_poll(username).await {
+            self.update(github).await;
+        }
+
+        let now = Timestamp::now();
+        let budgets = self.0.lock().await;
+        let Some(budget) = budgets.get(username) else {
+            return NextRefresh {
+                at: now + refresh_rate,
+                rate_limited: false,
+            };
+        };
+
+        // the first half of the budget goes at the normal rate
+        let mut wait = match (budget.fraction_left, budget.reset) {
+            (Some(fraction), Some(reset)) => refresh_rate
+                .div_f64((fraction * 2.0).clamp(0.01, 1.0))
+                .min(reset.duration_since(now))
+                .max(refresh_rate),
+            _ => refresh_rate,
+        };
+        if let Some((until, _)) = budget.backoff {
+            wait = wait.max(until.duration_since(now));
+        }
+
+        NextRefresh {
+            at: now + wait,
+            rate_limited: wait > refresh_rate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::eyre;
+    use jiff::SignedDuration;
+    use reqwest::{StatusCode, header::HeaderMap};
+
+    use super::{RateLimited, rate_limit};
+
+    #[test]
+    fn from_response_headers() {
+        let mut headers = HeaderMap::new();
+        assert!(RateLimited::from_response(StatusCode::FORBIDDEN, &headers).is_none());
+        assert!(RateLimited::from_response(StatusCode::TOO_MANY_REQUESTS, &headers).is_some());
+
+        headers.insert("retry-after", "60".parse().unwrap());
+        let rate_limited = RateLimited::from_response(StatusCode::FORBIDDEN, &headers).unwrap();
+        assert_eq!(
+            rate_limited.retry_after,
+            Some(SignedDuration::from_secs(60))
+        );
+
+        assert!(RateLimited::from_response(StatusCode::OK, &headers).is_none());
+    }
+
+    #[test]
+    fn only_typed_errors_are_rate_limits() {
+        let error = eyre!("rate limit poll timed out").wrap_err("get rate limit");
+        assert!(rate_limit(&error).is_none());
+
+        let error =
+            color_eyre::Report::new(RateLimited { retry_after: None }).wrap_err("graphql request");
+        assert!(rate_limit(&error).is_some());
+    }
+}
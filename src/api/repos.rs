@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use color_eyre::eyre::Context;
+use serde::Deserialize;
+use url::Url;
+
+use crate::model::{BorsBackend, Repo, RepoInfo};
+
+/// One entry of the config file, like
+/// `{ "owner": "rust-lang", "name": "cargo", "bors": { "new_bors": { "api_url": "https://bors.rust-lang.org/", "repo_name": "cargo" } } }`
+#[derive(Deserialize)]
+struct RepoConfig {
+    owner: String,
+    name: String,
+    /// Repos without a merge queue leave this out
+    #[serde(default)]
+    bors: Option<BorsBackend>,
+}
+
+/// What we track without a config file
+fn default_repos() -> Vec<RepoInfo> {
+    vec![RepoInfo {
+        repo: Repo {
+            owner: "rust-lang".to_string(),
+            name: "rust".to_string(),
+        },
+        bors: Some(BorsBackend::Homu {
+            queue_url: Url::parse("https://bors.rust-lang.org/queue/rust").unwrap(),
+        }),
+    }]
+}
+
+/// Reads a JSON list of repos and the bors that merges them
+pub fn load(path: Option<&Path>) -> color_eyre::Result<Vec<RepoInfo>> {
+    let Some(path) = path else {
+        return Ok(default_repos());
+    };
+
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("read repos from {}", path.display()))?;
+    let repos: Vec<RepoConfig> = serde_json::from_str(&contents).context("parse repos")?;
+
+    Ok(repos
+        .into_iter()
+        .map(|i| RepoInfo {
+            repo: Repo {
+                owner: i.owner,
+                name: i.name,
+            },
+            bors: i.bors,
+        })
+        .collect())
+}
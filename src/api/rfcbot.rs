@@ -1,13 +1,18 @@
 use std::collections::HashMap;
 
 use color_eyre::eyre::Context;
-use jiff::civil::DateTime;
+use jiff::{civil::DateTime, tz::TimeZone};
 use serde::Deserialize;
+use url::Url;
+
+use crate::model::{Author, FcpConcern, FcpDisposition, FcpStatus, FcpTodo, FcpTodoReason, Repo};
 
 #[derive(Deserialize)]
 pub struct Issue {
     pub number: u64,
     pub repository: String,
+    pub title: String,
+    pub is_pull_request: bool,
 
     #[serde(flatten)]
     pub other: HashMap<String, serde_json::Value>,
@@ -22,6 +27,15 @@ pub struct GitHubUser {
     pub other: HashMap<String, serde_json::Value>,
 }
 
+impl GitHubUser {
+    pub fn to_author(&self) -> Author {
+        Author {
+            id: self.id as u64,
+            ..Author::from_username(self.login.clone())
+        }
+    }
+}
+
 #[derive(Deserialize)]
 pub struct FcpProposal {
     pub disposition: String,
@@ -45,14 +59,94 @@ pub struct DeserializedFcpWithInfo {
 }
 
 pub struct FcpInfo {
+    pub title: String,
+    pub is_pull_request: bool,
     pub proposal: FcpProposal,
     pub reviews: Vec<(GitHubUser, bool)>,
     pub concerns: Vec<(String, GitHubUser)>,
 }
 
-pub type FcpInfoAll = HashMap<u64, FcpInfo>;
+impl FcpInfo {
+    pub fn status(&self) -> FcpStatus {
+        FcpStatus {
+            // rfcbot stores the start of the FCP as a naive UTC datetime
+            start: self
+                .proposal
+                .fcp_start
+                .and_then(|start| start.to_zoned(TimeZone::UTC).ok())
+                .map(|start| start.timestamp()),
+            disposition: match self.proposal.disposition.as_str() {
+                "merge" => FcpDisposition::Merge,
+                "close" => FcpDisposition::Close,
+                "postpone" => FcpDisposition::Postpone,
+                other => FcpDisposition::Other(other.to_string()),
+            },
+            pending_reviewers: self
+                .reviews
+                .iter()
+                .filter(|(_, checked)| !checked)
+                .map(|(user, _)| user.to_author())
+                .collect(),
+            concerns: self
+                .concerns
+                .iter()
+                .map(|(name, user)| FcpConcern {
+                    name: name.clone(),
+                    raised_by: user.to_author(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// FCPs by repository and issue number
+pub type FcpInfoAll = HashMap<(Repo, u64), FcpInfo>;
+
+const URL: &str = "https://rfcbot.rs/api/all";
+
+/// All FCPs where `username` has to check their box, or raised a concern that's still open
+pub fn fcp_todos_for_user(fcp_info: &FcpInfoAll, username: &str) -> Vec<FcpTodo> {
+    fcp_info
+        .iter()
+        .filter_map(|((repo, number), fcp)| {
+            let is_user = |user: &GitHubUser| user.login.eq_ignore_ascii_case(username);
 
-const URL: &str = "https://crater.rust-lang.org/";
+            let concerns = fcp
+                .concerns
+                .iter()
+                .filter(|(_, user)| is_user(user))
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<_>>();
+
+            let reason = if !concerns.is_empty() {
+                FcpTodoReason::Concerns(concerns)
+            } else if fcp
+                .reviews
+                .iter()
+                .any(|(user, checked)| is_user(user) && !checked)
+            {
+                FcpTodoReason::Checkbox
+            } else {
+                return None;
+            };
+
+            let kind = if fcp.is_pull_request {
+                "pull"
+            } else {
+                "issues"
+            };
+
+            Some(FcpTodo {
+                repo: repo.clone(),
+                title: fcp.title.clone(),
+                number: *number,
+                link: Url::parse(&format!("https://github.com/{repo}/{kind}/{number}")).unwrap(),
+                reason,
+                status: fcp.status(),
+            })
+        })
+        .collect()
+}
 
 pub async fn get_fcp_info() -> color_eyre::Result<FcpInfoAll> {
     let response = reqwest::get(URL).await?;
@@ -60,15 +154,28 @@ pub async fn get_fcp_info() -> color_eyre::Result<FcpInfoAll> {
 
     Ok(body
         .into_iter()
-        .map(|fcp| {
-            (
-                fcp.issue.number,
+        .filter(|fcp| !fcp.fcp.fcp_closed)
+        .filter_map(|fcp| {
+            let Some((owner, name)) = fcp.issue.repository.split_once('/') else {
+                tracing::error!("weird rfcbot repository: {}", fcp.issue.repository);
+                return None;
+            };
+
+            let repo = Repo {
+                owner: owner.to_string(),
+                name: name.to_string(),
+            };
+
+            Some((
+                (repo, fcp.issue.number),
                 FcpInfo {
+                    title: fcp.issue.title,
+                    is_pull_request: fcp.issue.is_pull_request,
                     proposal: fcp.fcp,
                     reviews: fcp.reviews,
                     concerns: fcp.concerns.into_iter().map(|i| (i.0, i.2)).collect(),
                 },
-            )
+            ))
         })
         .collect())
 }
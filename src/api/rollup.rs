@@ -1,15 +1,20 @@
 use color_eyre::eyre::Context;
-use octocrab::Octocrab;
+use jiff::Timestamp;
 use url::Url;
 
 use crate::{
     api::{
+        PrCache,
         bors::{BorsQueue, BorsStatus},
-        github::get_pr,
+        client::GitHubClient,
+        github::get_prs,
     },
-    model::Repo,
+    model::{Check, GitHubPr, Repo, RollupSetting},
 };
 
+/// Roughly how many PRs reviewers put in a rollup
+const MAX_ROLLUP_SIZE: usize = 12;
+
 #[derive(Debug, Clone)]
 #[allow(unused)]
 pub struct Rollup {
@@ -19,42 +24,88 @@ pub struct Rollup {
     pub position_in_queue: usize,
     pub pr_numbers: Vec<u64>,
     pub status: BorsStatus,
+    pub updated_at: Timestamp,
+}
+
+impl Rollup {
+    /// Failed rollups get closed, their PRs go back into the queue on their own
+    pub fn is_pending(&self) -> bool {
+        matches!(
+            self.status,
+            BorsStatus::Pending | BorsStatus::Success | BorsStatus::Approved
+        )
+    }
 }
 
 #[derive(Default, Debug, Clone)]
 pub struct RollupQueue {
     pub rollups: Vec<Rollup>,
+    /// PRs that will likely go into the next rollup, which doesn't exist yet
+    pub candidates: Vec<u64>,
+}
+
+/// The PRs a reviewer would pick for a new rollup: approved and mergeable `rollup=always` PRs
+/// that aren't in a rollup yet, in queue order. `rollup=iffy` PRs fill up whatever room is left.
+fn next_rollup_candidates(bors_queue: &BorsQueue, rollups: &[Rollup]) -> Vec<u64> {
+    let available = bors_queue
+        .items
+        .iter()
+        .filter(|i| !i.try_build && i.mergeable && i.status == BorsStatus::Approved)
+        .filter(|i| !i.title.starts_with("Rollup of"))
+        .filter(|i| {
+            !rollups
+                .iter()
+                .any(|r| r.is_pending() && r.pr_numbers.contains(&i.pr_number))
+        });
+
+    let (always, iffy): (Vec<_>, Vec<_>) = available
+        .filter(|i| {
+            matches!(
+                i.rollup_setting,
+                RollupSetting::Always | RollupSetting::Iffy
+            )
+        })
+        .partition(|i| matches!(i.rollup_setting, RollupSetting::Always));
+
+    always
+        .into_iter()
+        .chain(iffy)
+        .take(MAX_ROLLUP_SIZE)
+        .map(|i| i.pr_number)
+        .collect()
 }
 
+/// Rollups are fetched again only when they were updated, most of the time they come from the
+/// PRs shared between users
 pub async fn find_rollups(
-    octocrab: Octocrab,
+    github: GitHubClient,
+    shared_prs: &PrCache<GitHubPr>,
+    checks: &PrCache<Vec<Check>, String>,
     repo: Repo,
     bors_queue: &BorsQueue, // pr_number: u64,
 ) -> color_eyre::Result<RollupQueue> {
     let mut res = RollupQueue::default();
 
-    for pr in &bors_queue.items {
-        if !pr.title.starts_with("Rollup of") {
-            continue;
-        }
-
-        let gh_pr = get_pr(&octocrab, repo.clone(), pr.pr_number)
-            .await
-            .context("get PR")?;
+    let rollups = bors_queue
+        .items
+        .iter()
+        .filter(|i| i.title.starts_with("Rollup of"))
+        .collect::<Vec<_>>();
 
-        let Some(body) = gh_pr.body else {
-            tracing::error!("no body");
-            continue;
-        };
+    let pr_numbers = rollups.iter().map(|i| i.pr_number).collect::<Vec<_>>();
+    let mut gh_prs = get_prs(&github, shared_prs, checks, &repo, &pr_numbers)
+        .await
+        .context("get rollup PRs")?;
 
-        let Some(html_url) = gh_pr.html_url else {
-            tracing::error!("no url");
+    for pr in rollups {
+        let Some(gh_pr) = gh_prs.remove(&pr.pr_number) else {
+            tracing::error!("rollup #{} not found", pr.pr_number);
             continue;
         };
 
         let mut pr_numbers = Vec::new();
 
-        for i in body.lines() {
+        for i in gh_pr.issue.body.as_deref().unwrap_or_default().lines() {
             if let Some(line) = i.trim().strip_prefix("- ")
                 && let Some((_repo, rest)) = line.split_once("#")
                 && let Some((number, _description)) = rest.split_once(" ")
@@ -69,10 +120,111 @@ pub async fn find_rollups(
             running: pr.position_in_queue == 1,
             position_in_queue: pr.position_in_queue,
             pr_numbers,
-            pr_link: html_url,
+            pr_link: gh_pr.issue.url,
             status: pr.status.clone(),
+            updated_at: gh_pr.issue.updated_at,
         });
     }
 
+    res.candidates = next_rollup_candidates(bors_queue, &res.rollups);
+
     Ok(res)
 }
+
+#[cfg(test)]
+mod tests {
+    use jiff::Timestamp;
+
+    use super::{MAX_ROLLUP_SIZE, Rollup, next_rollup_candidates};
+    use crate::{
+        api::bors::{BorsPr, BorsQueue, BorsStatus, test_queue_url},
+        model::RollupSetting,
+    };
+
+    fn pr(pr_number: u64, rollup_setting: RollupSetting) -> BorsPr {
+        BorsPr {
+            rollup_setting,
+            ..BorsPr::test(pr_number)
+        }
+    }
+
+    #[test]
+    fn always_before_iffy() {
+        let queue = BorsQueue::test(vec![
+            pr(1, RollupSetting::Iffy),
+            pr(2, RollupSetting::Always),
+            pr(3, RollupSetting::Never),
+            pr(4, RollupSetting::Unset),
+            pr(5, RollupSetting::Always),
+        ]);
+
+        assert_eq!(next_rollup_candidates(&queue, &[]), [2, 5, 1]);
+    }
+
+    #[test]
+    fn only_approved_and_mergeable() {
+        let mut conflicted = pr(2, RollupSetting::Always);
+        conflicted.mergeable = false;
+        let mut try_build = pr(3, RollupSetting::Always);
+        try_build.try_build = true;
+        let mut failed = pr(4, RollupSetting::Always);
+        failed.status = BorsStatus::Failure;
+        let mut rollup = pr(5, RollupSetting::Always);
+        rollup.title = "Rollup of 3 pull requests".to_string();
+
+        let queue = BorsQueue::test(vec![
+            pr(1, RollupSetting::Always),
+            conflicted,
+            try_build,
+            failed,
+            rollup,
+        ]);
+
+        assert_eq!(next_rollup_candidates(&queue, &[]), [1]);
+    }
+
+    #[test]
+    fn not_in_a_pending_rollup() {
+        let queue = BorsQueue::test(vec![
+            pr(1, RollupSetting::Always),
+            pr(2, RollupSetting::Always),
+            pr(3, RollupSetting::Always),
+        ]);
+        let rollup = |status, pr_numbers| Rollup {
+            pr_number: 10,
+            pr_link: test_queue_url(),
+            running: false,
+            position_in_queue: 1,
+            pr_numbers,
+            status,
+            updated_at: Timestamp::UNIX_EPOCH,
+        };
+
+        // members of a failed rollup go back into the queue
+        assert_eq!(
+            next_rollup_candidates(
+                &queue,
+                &[
+                    rollup(BorsStatus::Pending, vec![1]),
+                    rollup(BorsStatus::Failure, vec![2])
+                ]
+            ),
+            [2, 3]
+        );
+    }
+
+    #[test]
+    fn capped_at_rollup_size() {
+        let queue = BorsQueue::test(
+            (1..=MAX_ROLLUP_SIZE as u64 + 5)
+                .map(|i| pr(i, RollupSetting::Iffy))
+                .chain([pr(100, RollupSetting::Always)])
+                .collect(),
+        );
+
+        let candidates = next_rollup_candidates(&queue, &[]);
+        assert_eq!(candidates.len(), MAX_ROLLUP_SIZE);
+        assert_eq!(candidates[0], 100);
+        assert_eq!(candidates[MAX_ROLLUP_SIZE - 1], MAX_ROLLUP_SIZE as u64 - 1);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+
+use color_eyre::eyre::Context;
+use serde::Deserialize;
+use url::Url;
+
+use crate::{
+    api::{
+        bors::{AutoBuild, latest_auto_build},
+        checks::{failed_jobs, job_log},
+        client::GitHubClient,
+        rollup::Rollup,
+        spurious::SpuriousFailures,
+    },
+    model::Repo,
+};
+
+/// Logs are long, only this many failing jobs are searched for hints
+const MAX_JOBS: usize = 3;
+
+/// A failed rollup and which of its PRs probably broke it
+#[derive(Clone, Debug)]
+pub struct FailedRollup {
+    /// The failing job, or the build bors linked to if we couldn't find it
+    pub log_url: Option<Url>,
+    pub job_name: Option<String>,
+    /// PR numbers with how many hints in the log point at them, most likely culprit first
+    pub culprits: Vec<(u64, usize)>,
+}
+
+#[derive(Deserialize)]
+struct ChangedFile {
+    filename: String,
+}
+
+async fn changed_files(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_number: u64,
+) -> color_eyre::Result<Vec<String>> {
+    let route = format!(
+        "/repos/{}/{}/pulls/{pr_number}/files",
+        repo.owner, repo.name
+    );
+
+    let files: Vec<ChangedFile> = github
+        .request(async |octocrab| {
+            octocrab
+                .get(route, Some(&[("per_page", 100)]))
+                .await
+                .context("get changed files")
+        })
+        .await?;
+
+    Ok(files.into_iter().map(|i| i.filename).collect())
+}
+
+/// The lines of a CI log that say what went wrong, like failing tests and compile errors
+fn failure_lines(log: &str) -> String {
+    log.lines()
+        .filter(|line| {
+            let line = line.to_lowercase();
+            line.contains("error")
+                || line.contains("failed")
+                || line.contains("panicked")
+                || line.contains("---- ")
+                || line.contains("--> ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A changed file mentioned in the failure counts most, its directory a bit
+fn score(files: &[String], failures: &str) -> usize {
+    files
+        .iter()
+        .map(|file| {
+            let directory = file.rsplit_once('/').map(|(dir, _)| dir);
+
+            if failures.contains(file.as_str()) {
+                3
+            } else if let Some(directory) = directory
+                && failures.contains(&format!("{directory}/"))
+            {
+                1
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+/// Looks at the failing CI jobs of the merge commit bors tested for a rollup,
+/// and ranks its PRs by how much of what they changed shows up in the failures.
+pub async fn find_culprits(
+    github: &GitHubClient,
+    spurious_failures: &SpuriousFailures,
+    repo: &Repo,
+    rollup: &Rollup,
+) -> color_eyre::Result<Option<FailedRollup>> {
+    let Some(AutoBuild {
+        merge_commit,
+        failure_link,
+    }) = latest_auto_build(github, repo, rollup.pr_number).await?
+    else {
+        return Ok(None);
+    };
+
+    let failed_jobs = failed_jobs(github, repo, &merge_commit).await?;
+
+    let mut failures = String::new();
+    let mut spurious = false;
+    for job in failed_jobs.iter().take(MAX_JOBS) {
+        match job_log(github, repo, job.id).await {
+            Ok(log) => {
+                spurious |= spurious_failures.classify(repo, &log).is_some();
+                failures.push_str(&failure_lines(&log));
+                failures.push('\n');
+            }
+            Err(e) => tracing::error!("error getting log of job {}: {e}", job.name),
+        }
+    }
+
+    // a known spurious failure isn't anyone's fault
+    let mut scores = HashMap::new();
+    if !failures.is_empty() && !spurious {
+        for pr_number in &rollup.pr_numbers {
+            // one PR we can't look at still leaves the others to rank
+            match changed_files(github, repo, *pr_number).await {
+                Ok(files) => {
+                    scores.insert(*pr_number, score(&files, &failures));
+                }
+                Err(e) => tracing::error!("error getting changed files of #{pr_number}: {e}"),
+            }
+        }
+    }
+
+    let mut culprits = scores
+        .into_iter()
+        .filter(|(_, score)| *score > 0)
+        .collect::<Vec<_>>();
+    culprits.sort_by_key(|(pr_number, score)| (std::cmp::Reverse(*score), *pr_number));
+
+    let first_job = failed_jobs.first();
+
+    Ok(Some(FailedRollup {
+        log_url: first_job.and_then(|i| i.html_url.clone()).or(failure_link),
+        job_name: first_job.map(|i| i.name.clone()),
+        culprits,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{failure_lines, score};
+
+    const LOG: &str = include_str!("fixtures/rollup_job_log.txt");
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|i| i.to_string()).collect()
+    }
+
+    #[test]
+    fn failure_lines_skip_the_noise() {
+        let failures = failure_lines(LOG);
+
+        assert!(failures.contains("---- [ui] tests/ui/borrowck/two-phase-reservation.rs stdout"));
+        assert!(failures.contains("panicked at src/tools/compiletest/src/runtest.rs"));
+        assert!(failures.contains("##[error]Process completed"));
+        assert!(!failures.contains("Building stage1"));
+        assert!(!failures.contains("issue-64130"));
+    }
+
+    #[test]
+    fn changed_test_scores_highest() {
+        let failures = failure_lines(LOG);
+
+        // the failing test itself
+        assert_eq!(
+            score(
+                &files(&["tests/ui/borrowck/two-phase-reservation.rs"]),
+                &failures
+            ),
+            3
+        );
+        // only the directory
+        assert_eq!(
+            score(
+                &files(&["tests/ui/borrowck/two-phase-activation.rs"]),
+                &failures
+            ),
+            1
+        );
+        // both, and one that's unrelated
+        assert_eq!(
+            score(
+                &files(&[
+                    "tests/ui/borrowck/two-phase-reservation.stderr",
+                    "tests/ui/borrowck/two-phase-reservation.rs",
+                    "library/core/src/option.rs",
+                ]),
+                &failures
+            ),
+            4
+        );
+    }
+
+    #[test]
+    fn unrelated_changes_score_nothing() {
+        let failures = failure_lines(LOG);
+
+        assert_eq!(
+            score(
+                &files(&["library/core/src/option.rs", "README.md"]),
+                &failures
+            ),
+            0
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use jiff::Timestamp;
+use tokio::sync::Mutex;
+
+use crate::model::{Repo, Source, SourceHealth};
+
+#[derive(Default)]
+struct RepoScrape {
+    last_success: Option<Timestamp>,
+    last_error: Option<String>,
+    items: Option<usize>,
+    warnings: Vec<String>,
+}
+
+/// Whose token was used, whose PRs were scraped, and from where
+type Key = (String, String, Repo);
+
+/// How getting a user's PRs from GitHub went for each repo, the last time they were scraped. It's
+/// kept per token owner as well, since every token has its own rate limit, so one user being
+/// rate limited while looking at someone else's PRs says nothing about how it goes for that
+/// someone else.
+#[derive(Default)]
+pub struct ScrapeHealth(Mutex<HashMap<Key, RepoScrape>>);
+
+fn key(token_owner: &str, username: &str, repo: &Repo) -> Key {
+    (token_owner.to_string(), username.to_string(), repo.clone())
+}
+
+impl ScrapeHealth {
+    pub async fn succeeded(
+        &self,
+        token_owner: &str,
+        username: &str,
+        repo: &Repo,
+        items: usize,
+        warnings: Vec<String>,
+    ) {
+        let mut repos = self.0.lock().await;
+        let scrape = repos.entry(key(token_owner, username, repo)).or_default();
+
+        scrape.last_success = Some(Timestamp::now());
+        scrape.last_error = None;
+        scrape.items = Some(items);
+        scrape.warnings = warnings;
+    }
+
+    pub async fn failed(
+        &self,
+        token_owner: &str,
+        username: &str,
+        repo: &Repo,
+        error: &color_eyre::Report,
+    ) {
+        self.0
+            .lock()
+            .await
+            .entry(key(token_owner, username, repo))
+            .or_default()
+            .last_error = Some(format!("{error:#}"));
+    }
+
+    pub async fn health(&self, token_owner: &str, username: &str, repo: &Repo) -> SourceHealth {
+        let repos = self.0.lock().await;
+        let scrape = repos.get(&key(token_owner, username, repo));
+
+        SourceHealth {
+            source: Source::GitHub,
+            repo: Some(repo.clone()),
+            last_success: scrape.and_then(|i| i.last_success),
+            last_error: scrape.and_then(|i| i.last_error.clone()),
+            items: scrape.and_then(|i| i.items),
+            warnings: scrape.map(|i| i.warnings.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use color_eyre::eyre::eyre;
+
+    use super::ScrapeHealth;
+    use crate::model::Repo;
+
+    #[tokio::test]
+    async fn failures_are_per_user() {
+        let health = ScrapeHealth::default();
+        let repo = Repo {
+            owner: "rust-lang".to_string(),
+            name: "rust".to_string(),
+        };
+
+        health
+            .succeeded("alice", "alice", &repo, 3, Vec::new())
+            .await;
+        health.succeeded("bob", "bob", &repo, 5, Vec::new()).await;
+        health
+            .failed("bob", "bob", &repo, &eyre!("rate limited"))
+            .await;
+
+        let alice = health.health("alice", "alice", &repo).await;
+        assert_eq!(alice.last_error, None);
+        assert_eq!(alice.items, Some(3));
+
+        let bob = health.health("bob", "bob", &repo).await;
+        assert_eq!(bob.last_error.as_deref(), Some("rate limited"));
+        assert_eq!(bob.items, Some(5));
+
+        assert_eq!(
+            health.health("carol", "carol", &repo).await.last_success,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn failures_are_per_token_owner() {
+        let health = ScrapeHealth::default();
+        let repo = Repo {
+            owner: "rust-lang".to_string(),
+            name: "rust".to_string(),
+        };
+
+        // alice is rate limited while looking at bob's PRs
+        health.succeeded("bob", "bob", &repo, 5, Vec::new()).await;
+        health
+            .failed("alice", "bob", &repo, &eyre!("rate limited"))
+            .await;
+
+        assert_eq!(health.health("bob", "bob", &repo).await.last_error, None);
+        assert_eq!(
+            health
+                .health("alice", "bob", &repo)
+                .await
+                .last_error
+                .as_deref(),
+            Some("rate limited")
+        );
+    }
+}
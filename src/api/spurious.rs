@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use color_eyre::eyre::{Context, eyre};
+use regex::Regex;
+use serde::Deserialize;
+use url::Url;
+
+use crate::model::{Repo, SpuriousFailure};
+
+/// One entry of the config file, like
+/// `{ "pattern": "error: could not download file", "issue": 12345, "repo": "rust-lang/rust" }`
+#[derive(Deserialize)]
+struct PatternConfig {
+    pattern: String,
+    /// The issue tracking this failure, in the repo the failure happened in
+    issue: u64,
+    /// `owner/name` of the only repo the pattern applies to, otherwise it applies to all of them
+    #[serde(default)]
+    repo: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct Pattern {
+    regex: Regex,
+    issue: u64,
+    repo: Option<Repo>,
+}
+
+/// Log patterns of CI failures that are known to be spurious, so a retry is all it takes
+#[derive(Clone, Debug, Default)]
+pub struct SpuriousFailures(Vec<Pattern>);
+
+impl SpuriousFailures {
+    /// Reads a JSON list of patterns. Without a config file nothing is classified as spurious.
+    pub fn load(path: Option<&Path>) -> color_eyre::Result<Self> {
+        let Some(path) = path else {
+            return Ok(Self::default());
+        };
+
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("read spurious failures from {}", path.display()))?;
+
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> color_eyre::Result<Self> {
+        let patterns: Vec<PatternConfig> =
+            serde_json::from_str(contents).context("parse spurious failures")?;
+
+        patterns
+            .into_iter()
+            .map(|i| {
+                let repo = i
+                    .repo
+                    .map(|repo| match repo.split_once('/') {
+                        Some((owner, name)) => Ok(Repo {
+                            owner: owner.to_string(),
+                            name: name.to_string(),
+                        }),
+                        None => Err(eyre!("repo of issue #{} isn't owner/name", i.issue)),
+                    })
+                    .transpose()?;
+
+                Ok(Pattern {
+                    regex: Regex::new(&i.pattern)
+                        .with_context(|| format!("invalid pattern for issue #{}", i.issue))?,
+                    issue: i.issue,
+                    repo,
+                })
+            })
+            .collect::<color_eyre::Result<_>>()
+            .map(Self)
+    }
+
+    /// The first known spurious failure that shows up in a CI log
+    pub fn classify(&self, repo: &Repo, log: &str) -> Option<SpuriousFailure> {
+        let pattern = self
+            .0
+            .iter()
+            .filter(|i| i.repo.as_ref().is_none_or(|i| i == repo))
+            .find(|i| i.regex.is_match(log))?;
+
+        Some(SpuriousFailure {
+            issue: pattern.issue,
+            issue_url: Url::parse(&format!(
+                "https://github.com/{repo}/issues/{}",
+                pattern.issue
+            ))
+            .unwrap(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpuriousFailures;
+    use crate::model::Repo;
+
+    const CONFIG: &str = r#"[
+        { "pattern": "error: could not download file from '.*'", "issue": 40474 },
+        { "pattern": "No space left on device", "issue": 110820, "repo": "rust-lang/rust" }
+    ]"#;
+
+    fn repo(owner: &str, name: &str) -> Repo {
+        Repo {
+            owner: owner.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn global_pattern_matches_every_repo() {
+        let failures = SpuriousFailures::parse(CONFIG).unwrap();
+        let log =
+            "downloading\nerror: could not download file from 'https://static.rust-lang.org/x'\n";
+
+        let rust = failures.classify(&repo("rust-lang", "rust"), log).unwrap();
+        assert_eq!(rust.issue, 40474);
+        assert_eq!(
+            rust.issue_url.as_str(),
+            "https://github.com/rust-lang/rust/issues/40474"
+        );
+
+        let cargo = failures.classify(&repo("rust-lang", "cargo"), log).unwrap();
+        assert_eq!(
+            cargo.issue_url.as_str(),
+            "https://github.com/rust-lang/cargo/issues/40474"
+        );
+    }
+
+    #[test]
+    fn repo_pattern_only_matches_its_repo() {
+        let failures = SpuriousFailures::parse(CONFIG).unwrap();
+        let log = "writing artifacts\nfatal error: No space left on device\n";
+
+        let rust = failures.classify(&repo("rust-lang", "rust"), log).unwrap();
+        assert_eq!(rust.issue, 110820);
+
+        assert!(
+            failures
+                .classify(&repo("rust-lang", "cargo"), log)
+                .is_none()
+        );
+        assert!(
+            failures
+                .classify(&repo("rust-lang", "rust"), "test result: FAILED")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn repo_must_be_owner_and_name() {
+        let config = r#"[{ "pattern": "x", "issue": 1, "repo": "rust" }]"#;
+        assert!(SpuriousFailures::parse(config).is_err());
+    }
+}
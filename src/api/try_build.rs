@@ -0,0 +1,83 @@
+use color_eyre::eyre::Context;
+use jiff::Timestamp;
+
+use crate::{
+    api::{
+        bors::{BORS_USERS, first_link},
+        checks::{CheckRuns, check_runs},
+        client::GitHubClient,
+        github::recent_comments,
+    },
+    model::{Repo, TryBuildState, TryBuildStatus},
+};
+
+/// Finds the latest try build on a PR from bors' comments,
+/// and the state of the checks running on the try commit.
+pub async fn get_try_build(
+    github: &GitHubClient,
+    repo: &Repo,
+    pr_number: u64,
+) -> color_eyre::Result<Option<TryBuildStatus>> {
+    let comments = recent_comments(github, repo, pr_number)
+        .await
+        .context("get comments")?;
+
+    let bors_comments = comments
+        .iter()
+        .filter(|c| BORS_USERS.contains(&c.user.login.as_str()))
+        .filter_map(|c| Some((c, c.body.as_deref()?)));
+
+    let mut res = None;
+
+    for (comment, body) in bors_comments {
+        // ":hourglass: Trying commit <head> with merge <merge>..."
+        if let Some((_, rest)) = body.split_once("Trying commit ")
+            && let Some((_head, rest)) = rest.split_once(" with merge ")
+        {
+            let commit = rest
+                .chars()
+                .take_while(|c| c.is_ascii_hexdigit())
+                .collect::<String>();
+
+            res = Some(TryBuildStatus {
+                commit,
+                started: Timestamp::from_second(comment.created_at.timestamp()).unwrap(),
+                state: TryBuildState::Running,
+                url: None,
+            });
+        } else if let Some(status) = &mut res
+            && let TryBuildState::Running = status.state
+        {
+            if body.contains("Try build successful") {
+                status.state = TryBuildState::Succeeded;
+                status.url = first_link(body);
+            } else if body.contains("Test failed") || body.contains("Build cancelled") {
+                status.state = TryBuildState::Failed;
+                status.url = first_link(body);
+            }
+        }
+    }
+
+    // bors hasn't reported back yet, look at the checks on the try commit itself
+    if let Some(status) = &mut res
+        && let TryBuildState::Running = status.state
+        && !status.commit.is_empty()
+    {
+        match check_runs(github, repo, &status.commit).await {
+            Ok(CheckRuns { check_runs }) => {
+                status.url = check_runs.iter().find_map(|i| i.html_url.clone());
+
+                if check_runs.iter().any(|i| i.failed()) {
+                    status.state = TryBuildState::Failed;
+                } else if !check_runs.is_empty()
+                    && check_runs.iter().all(|i| i.status == "completed")
+                {
+                    status.state = TryBuildState::Succeeded;
+                }
+            }
+            Err(e) => tracing::error!("error getting try build checks: {e}"),
+        }
+    }
+
+    Ok(res)
+}
@@ -6,7 +6,7 @@ use rust_query::{
 };
 
 #[schema(Schema)]
-#[version(0..=1)]
+#[version(0..=2)]
 pub mod vN {
     pub struct User {
         #[unique]
@@ -19,6 +19,11 @@ pub mod vN {
         pub sequence_number: i64,
 
         pub refresh_rate_seconds: i64,
+
+        /// JSON of what the last refresh saw in each repo, so a restart doesn't list and get
+        /// every PR again
+        #[version(2..)]
+        pub refresh_state: String,
     }
 
     /// To keep a history of closed issues
@@ -38,7 +43,7 @@ pub mod vN {
     }
 }
 
-pub use v1::*;
+pub use v2::*;
 
 pub fn migrate(db_path: PathBuf) -> Database<Schema> {
     let m = Database::migrator(Config::open(db_path))
@@ -50,6 +55,12 @@ pub fn migrate(db_path: PathBuf) -> Database<Schema> {
         }),
     });
 
+    let m = m.migrate(|txn| v1::migrate::Schema {
+        user: txn.migrate_ok(|_: v1::User!(username)| v1::migrate::User {
+            refresh_state: String::new(),
+        }),
+    });
+
     m.finish()
         .expect("database is newer than supported versions")
 }
@@ -2,11 +2,15 @@
 
 use std::sync::Arc;
 
-use octocrab::Octocrab;
-use rust_query::Update;
+use rust_query::{FromExpr, Update};
 use tokio::{sync::Mutex, task::spawn_blocking};
 
-use crate::{AppState, db::User, model::RepoInfo};
+use crate::{
+    AppState,
+    api::{client::GitHubClient, github::RefreshState},
+    db::{MacroRoot, User},
+    model::RepoInfo,
+};
 
 #[derive(Debug)]
 pub struct LoginContext {
@@ -14,7 +18,7 @@ pub struct LoginContext {
     pub current_username: Mutex<String>,
 
     pub repos: Vec<RepoInfo>,
-    pub octocrab: Octocrab,
+    pub github: GitHubClient,
     pub state: Arc<AppState>,
 }
 
@@ -47,4 +51,72 @@ impl LoginContext {
     pub async fn username(&self) -> String {
         self.current_username.lock().await.clone()
     }
+
+    /// What the last refresh of the logged in user saw, with the ETags in it remembered again
+    pub async fn refresh_state(&self) -> RefreshState {
+        let stored = self.stored_refresh_state().await;
+        let mut refresh = match stored
+            .filter(|i| !i.is_empty())
+            .map(|i| serde_json::from_str(&i))
+        {
+            Some(Ok(refresh)) => refresh,
+            Some(Err(e)) => {
+                tracing::error!("stored refresh state of {}: {e}", self.base_username);
+                RefreshState::default()
+            }
+            None => RefreshState::default(),
+        };
+
+        // repos can be dropped from the config
+        refresh
+            .0
+            .retain(|i| self.repos.iter().any(|repo| repo.repo == i.repo));
+
+        for repo in &refresh.0 {
+            for (pr, etag) in &repo.prs {
+                if let Some(etag) = etag {
+                    self.state.etags.restore(&repo.repo, pr, etag).await;
+                }
+            }
+        }
+
+        refresh
+    }
+
+    async fn stored_refresh_state(&self) -> Option<String> {
+        let state = self.state.clone();
+        let base_username = self.base_username.clone();
+
+        spawn_blocking(move || {
+            state.db.transaction_mut_ok(|txn| {
+                let user = txn.query_one(User::unique(base_username))?;
+                let data: User!(refresh_state) = txn.query_one(FromExpr::from_expr(user));
+                Some(data.refresh_state)
+            })
+        })
+        .await
+        .unwrap()
+    }
+
+    pub async fn store_refresh_state(&self, refresh: &RefreshState) {
+        let refresh_state = serde_json::to_string(refresh).expect("serializable");
+        let state = self.state.clone();
+        let base_username = self.base_username.clone();
+
+        spawn_blocking(move || {
+            state.db.transaction_mut_ok(|txn| {
+                if let Some(user) = txn.query_one(User::unique(base_username)) {
+                    txn.update_ok(
+                        user,
+                        User {
+                            refresh_state: Update::set(refresh_state),
+                            ..Default::default()
+                        },
+                    );
+                }
+            });
+        })
+        .await
+        .unwrap();
+    }
 }
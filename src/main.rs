@@ -4,26 +4,41 @@ use axum::{
 };
 use color_eyre::eyre::Context;
 use futures::StreamExt;
-use octocrab::Octocrab;
+use jiff::Timestamp;
 use rust_query::{Database, IntoExpr, Update};
 use std::collections::HashMap;
 use std::fmt::Debug;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::{env, sync::Arc, time::Duration};
 use tokio::sync::{Mutex, OnceCell, RwLock};
 use tower_http::services::ServeDir;
 
 use crate::{
     api::{
-        Cache,
+        Cache, CacheStatus, PrCache,
         bors::{BorsQueue, get_bors_info},
-        github::scrape_github_for_user,
+        ci_failure::CiFailureVersion,
+        client::GitHubClient,
+        etags::Etags,
+        github::{PrFingerprint, PreviousPrs, RefreshState, scrape_github_for_user},
+        merge_times::MergeTimes,
+        rate_limit::RateLimits,
         rollup::find_rollups,
+        rollup_failure::FailedRollup,
+        scrape_health::ScrapeHealth,
+        spurious::SpuriousFailures,
+    },
+    model::{
+        Check, CiFailure, CraterStatus, FcpTodo, GitHubPr, PerfStatus, PerfSummary, Pr, QueueState,
+        RepoInfo, Source, SourceHealth, TryBuildStatus,
     },
-    model::{CraterStatus, Pr, RepoInfo},
 };
 use crate::{
-    api::{crater::get_crater_queue, rfcbot::get_fcp_info},
+    api::{
+        crater::get_crater_queue,
+        perf::get_perf_queue,
+        rfcbot::{fcp_todos_for_user, get_fcp_info},
+    },
     db::Schema,
 };
 use crate::{
@@ -31,7 +46,7 @@ use crate::{
     db::Issue,
     model::Repo,
 };
-use crate::{db::User, login_cx::LoginContext};
+use crate::{db::User, login_cx::LoginContext, sort::sort_repo_queue};
 
 mod api;
 mod db;
@@ -49,23 +64,50 @@ pub struct Config {
     pub host: String,
     pub oauth_client_id: String,
     pub oauth_client_secret: String,
+    pub spurious_failures: SpuriousFailures,
+    /// The repos everyone's dashboard covers
+    pub repos: Vec<RepoInfo>,
 }
 
 #[derive(Default)]
 struct UserState {
     prs: OnceCell<Vec<Pr>>,
     old: Vec<Pr>,
+    /// What each PR was sorted based on, so the next refresh only sorts the ones that changed
+    fingerprints: Mutex<HashMap<(Repo, u64), PrFingerprint>>,
+    /// So the next refresh only lists the PRs that were updated, loaded from the database on the
+    /// first refresh
+    refresh: OnceCell<Arc<Mutex<RefreshState>>>,
 }
 
+/// Everything in the bors queue of a repo, sorted
+type RepoQueueCache = Cache<Vec<Pr>, Arc<LoginContext>>;
+
 struct AppState {
     db: Database<Schema>,
     config: Config,
 
-    bors_info: Mutex<HashMap<Repo, Cache<'static, BorsQueue>>>,
-    rollup_info: Mutex<HashMap<Repo, Cache<'static, RollupQueue, Octocrab>>>,
-
-    crater_info: Cache<'static, HashMap<u64, CraterStatus>>,
-    fcp_info: Cache<'static, FcpInfoAll>,
+    bors_info: Mutex<HashMap<Repo, Cache<BorsQueue>>>,
+    merge_times: Arc<Mutex<HashMap<Repo, MergeTimes>>>,
+    rollup_info: Mutex<HashMap<Repo, Cache<RollupQueue, GitHubClient>>>,
+    /// Per token owner, since it's sorted with their token and private repos only show up for
+    /// those that can see them
+    repo_queues: Mutex<HashMap<(Repo, String), RepoQueueCache>>,
+
+    crater_info: Cache<HashMap<u64, CraterStatus>>,
+    perf_info: Cache<HashMap<u64, PerfStatus>>,
+    perf_summaries: PrCache<Option<PerfSummary>>,
+    crater_reports: PrCache<Option<CraterStatus>>,
+    rollup_failures: PrCache<Option<FailedRollup>>,
+    ci_failures: PrCache<Option<CiFailure>, CiFailureVersion>,
+    bors_failures: PrCache<Option<Timestamp>>,
+    try_builds: PrCache<Option<TryBuildStatus>>,
+    checks: PrCache<Vec<Check>, String>,
+    etags: Etags,
+    shared_prs: PrCache<GitHubPr>,
+    scrape_health: ScrapeHealth,
+    rate_limits: Arc<RateLimits>,
+    fcp_info: Cache<FcpInfoAll>,
 
     users_prs_by_username: RwLock<HashMap<String, UserState>>,
 }
@@ -79,6 +121,131 @@ async fn get_state_instantly(config: Arc<LoginContext>) -> Vec<Pr> {
         .unwrap_or_default()
 }
 
+async fn get_fcp_todos(config: Arc<LoginContext>) -> Vec<FcpTodo> {
+    let fcp_info = config.state.fcp_info.get().await;
+    fcp_todos_for_user(&fcp_info, &config.username().await)
+}
+
+/// Repos whose merge queue isn't accepting merges right now
+async fn get_closed_queues(config: Arc<LoginContext>) -> Vec<(Repo, QueueState)> {
+    let mut res = Vec::new();
+
+    for repo in &config.repos {
+        let queue = config.state.bors_info(repo.clone()).await;
+        if !queue.state.accepting_merges() {
+            res.push((repo.repo.clone(), queue.state.clone()));
+        }
+    }
+
+    res
+}
+
+fn cache_health(
+    source: Source,
+    repo: Option<Repo>,
+    status: CacheStatus,
+    items: usize,
+    warnings: Vec<String>,
+) -> SourceHealth {
+    SourceHealth {
+        source,
+        repo,
+        last_success: status
+            .age
+            .and_then(|age| Timestamp::now().checked_sub(age).ok()),
+        last_error: status.last_error,
+        items: Some(items),
+        warnings,
+    }
+}
+
+/// How each data source is doing, per repo for the sources that are per repo. GitHub is how it
+/// went scraping the user being looked at with the logged in user's token, the others are the
+/// same for everyone.
+async fn get_source_health(config: Arc<LoginContext>) -> Vec<SourceHealth> {
+    let state = &config.state;
+    let username = config.username().await;
+    let mut res = Vec::new();
+
+    for repo in &config.repos {
+        res.push(
+            state
+                .scrape_health
+                .health(config.github.username(), &username, repo)
+                .await,
+        );
+
+        if repo.bors.is_none() {
+            continue;
+        }
+        let queue = state.bors_info(repo.clone()).await;
+        let Some(status) = state.bors_info_status(repo).await else {
+            continue;
+        };
+
+        let report = &queue.parse_report;
+        let mut warnings = Vec::new();
+        if !report.missing_columns.is_empty() {
+            warnings.push(format!(
+                "missing columns: {}",
+                report.missing_columns.join(", ")
+            ));
+        }
+        if !report.skipped_rows.is_empty() {
+            warnings.push(format!("{} rows skipped", report.skipped_rows.len()));
+        }
+        if !report.warnings.is_empty() {
+            warnings.push(format!("{} rows partly read", report.warnings.len()));
+        }
+
+        res.push(cache_health(
+            Source::Bors,
+            Some(repo.repo.clone()),
+            status,
+            queue.items.len(),
+            warnings,
+        ));
+    }
+
+    let crater = state.crater_info.get().await;
+    res.push(cache_health(
+        Source::Crater,
+        None,
+        state.crater_info.status().await,
+        crater.len(),
+        Vec::new(),
+    ));
+    let perf = state.perf_info.get().await;
+    res.push(cache_health(
+        Source::Perf,
+        None,
+        state.perf_info.status().await,
+        perf.len(),
+        Vec::new(),
+    ));
+    let fcps = state.fcp_info.get().await;
+    res.push(cache_health(
+        Source::Rfcbot,
+        None,
+        state.fcp_info.status().await,
+        fcps.len(),
+        Vec::new(),
+    ));
+
+    res
+}
+
+/// Notes which PRs show something from a source that's out of date
+fn mark_stale(prs: &mut [Pr], health: &[SourceHealth]) {
+    for pr in prs {
+        pr.stale_sources = health
+            .iter()
+            .filter(|i| i.is_stale() && pr.depends_on(i))
+            .cloned()
+            .collect();
+    }
+}
+
 async fn update_prs_database(prs: &[Pr], username: String, config: Arc<LoginContext>) {
     if config.base_username != username {
         return;
@@ -135,10 +302,50 @@ async fn get_and_update_state(config: Arc<LoginContext>) -> Vec<Pr> {
     user_state
         .prs
         .get_or_init(async || {
-            let pr_stream = scrape_github_for_user(config.clone(), username.clone());
-            let prs: Vec<_> = pr_stream.collect().await;
+            let previous: PreviousPrs = {
+                let fingerprints = user_state.fingerprints.lock().await;
+                user_state
+                    .old
+                    .iter()
+                    .filter_map(|pr| {
+                        let key = (pr.repo.clone(), pr.number);
+                        let fingerprint = fingerprints.get(&key)?.clone();
+                        Some((key, (fingerprint, pr.clone())))
+                    })
+                    .collect()
+            };
+
+            // only the logged in user's is kept, like their issue history
+            let stored = config.base_username == username;
+            let refresh = user_state
+                .refresh
+                .get_or_init(async || {
+                    let refresh = if stored {
+                        config.refresh_state().await
+                    } else {
+                        RefreshState::default()
+                    };
+                    Arc::new(Mutex::new(refresh))
+                })
+                .await
+                .clone();
+
+            let pr_stream = scrape_github_for_user(
+                config.clone(),
+                username.clone(),
+                Arc::new(previous),
+                refresh.clone(),
+            );
+            let (fingerprints, prs): (HashMap<_, _>, Vec<_>) = pr_stream
+                .map(|(fingerprint, pr)| (((pr.repo.clone(), pr.number), fingerprint), pr))
+                .unzip()
+                .await;
+            *user_state.fingerprints.lock().await = fingerprints;
 
             update_prs_database(&prs, username.clone(), config.clone()).await;
+            if stored {
+                config.store_refresh_state(&*refresh.lock().await).await;
+            }
 
             prs
         })
@@ -161,80 +368,109 @@ impl AppState {
             crater_info: Cache::new(
                 async || {
                     tracing::info!("reloading crater info");
-                    match get_crater_queue().await {
-                        Ok(i) => i,
-                        Err(e) => {
-                            tracing::error!("crater error: {e}");
-                            Default::default()
-                        }
-                    }
+                    get_crater_queue()
+                        .await
+                        .inspect_err(|e| tracing::error!("crater error: {e}"))
                 },
                 Duration::from_secs(60 * 10),
             ),
+            perf_info: Cache::new(
+                async || {
+                    tracing::info!("reloading perf info");
+                    get_perf_queue()
+                        .await
+                        .inspect_err(|e| tracing::error!("perf error: {e}"))
+                },
+                Duration::from_secs(60 * 2),
+            ),
             fcp_info: Cache::new(
                 async || {
                     tracing::info!("reloading fcp info");
-                    match get_fcp_info().await {
-                        Ok(i) => i,
-                        Err(e) => {
-                            tracing::error!("crater error: {e}");
-                            Default::default()
-                        }
-                    }
+                    get_fcp_info()
+                        .await
+                        .inspect_err(|e| tracing::error!("fcp error: {e}"))
                 },
                 Duration::from_secs(60 * 2),
             ),
+            perf_summaries: PrCache::new(),
+            crater_reports: PrCache::new(),
+            rollup_failures: PrCache::new(),
+            ci_failures: PrCache::new(),
+            bors_failures: PrCache::new(),
+            try_builds: PrCache::new(),
+            checks: PrCache::new(),
+            etags: Etags::new(),
+            shared_prs: PrCache::new(),
+            scrape_health: ScrapeHealth::default(),
+            rate_limits: Arc::default(),
             bors_info: Mutex::new(HashMap::new()),
+            merge_times: Arc::new(Mutex::new(HashMap::new())),
             rollup_info: Mutex::new(HashMap::new()),
+            repo_queues: Mutex::new(HashMap::new()),
         }
     }
 
     pub async fn bors_info(&self, repo: RepoInfo) -> Arc<BorsQueue> {
         let RepoInfo {
             repo,
-            bors_queue_url: Some(url),
+            bors: Some(backend),
         } = repo
         else {
             return Arc::new(Default::default());
         };
 
-        self.bors_info
+        // don't keep other repos waiting while this one loads
+        let cache = self
+            .bors_info
             .lock()
             .await
             .entry(repo.clone())
             .or_insert_with(move || {
                 let repo = repo.clone();
-                let url = url.clone();
+                let backend = backend.clone();
+                let merge_times = self.merge_times.clone();
                 Cache::new(
                     move || {
                         let repo = repo.clone();
-                        let url = url.clone();
+                        let backend = backend.clone();
+                        let merge_times = merge_times.clone();
                         async move {
                             tracing::info!("reloading bors info for {repo}");
-                            match get_bors_info(url.clone()).await {
-                                Ok(i) => i,
-                                Err(e) => {
-                                    tracing::error!("bors queue error: {e}");
-                                    Default::default()
-                                }
-                            }
+                            let i = get_bors_info(backend)
+                                .await
+                                .inspect_err(|e| tracing::error!("bors queue error: {e}"))?;
+                            merge_times
+                                .lock()
+                                .await
+                                .entry(repo)
+                                .or_default()
+                                .observe(&i, Timestamp::now());
+                            Ok(i)
                         }
                     },
                     Duration::from_secs(60),
                 )
             })
-            .get()
-            .await
+            .clone();
+
+        cache.get().await
+    }
+
+    /// None until the bors queue of the repo was first needed
+    pub async fn bors_info_status(&self, repo: &Repo) -> Option<CacheStatus> {
+        let cache = self.bors_info.lock().await.get(repo).cloned()?;
+        Some(cache.status().await)
     }
 
     pub async fn rollup_info(
         self: Arc<Self>,
         repo: RepoInfo,
-        octocrab: Octocrab,
+        github: GitHubClient,
     ) -> Arc<RollupQueue> {
         let this = self.clone();
 
-        self.rollup_info
+        let cache = self
+            .rollup_info
             .lock()
             .await
             .entry(repo.repo.clone())
@@ -242,29 +478,66 @@ impl AppState {
                 let repo = repo.clone();
                 let this = this.clone();
                 Cache::new_with_param(
-                    move |octocrab: Octocrab| {
+                    move |github: GitHubClient| {
                         let repo = repo.clone();
-                        let octocrab = octocrab.clone();
+                        let github = github.clone();
                         let this = this.clone();
                         async move {
                             tracing::info!("reloading rollup info for {}", repo.repo);
 
                             let bors_queue = this.bors_info(repo.clone()).await;
 
-                            match find_rollups(octocrab, repo.repo, &*bors_queue).await {
-                                Ok(i) => i,
-                                Err(e) => {
-                                    tracing::error!("bors queue error: {e}");
-                                    Default::default()
-                                }
-                            }
+                            find_rollups(
+                                github,
+                                &this.shared_prs,
+                                &this.checks,
+                                repo.repo,
+                                &*bors_queue,
+                            )
+                            .await
+                            .inspect_err(|e| tracing::error!("rollup error: {e}"))
                         }
                     },
                     Duration::from_secs(60),
                 )
             })
-            .get_with_param(octocrab)
+            .clone();
+
+        cache.get_with_param(github).await
+    }
+
+    /// Sorting the whole queue takes a few requests, so it's only done once per bors reload for
+    /// every logged in user looking at it
+    pub async fn repo_queue(
+        &self,
+        repo: RepoInfo,
+        login_context: Arc<LoginContext>,
+    ) -> Arc<Vec<Pr>> {
+        let cache = self
+            .repo_queues
+            .lock()
             .await
+            .entry((
+                repo.repo.clone(),
+                login_context.github.username().to_string(),
+            ))
+            .or_insert_with(move || {
+                Cache::new_with_param(
+                    move |login_context: Arc<LoginContext>| {
+                        let repo = repo.clone();
+                        async move {
+                            tracing::info!("sorting the queue of {}", repo.repo);
+                            sort_repo_queue(login_context, repo)
+                                .await
+                                .inspect_err(|e| tracing::error!("repo queue error: {e}"))
+                        }
+                    },
+                    Duration::from_secs(60),
+                )
+            })
+            .clone();
+
+        cache.get_with_param(login_context).await
     }
 }
 
@@ -281,6 +554,13 @@ async fn main() -> color_eyre::Result<()> {
         oauth_client_id: env::var("OAUTH_CLIENT_ID").context("get `OAUTH_CLIENT_ID` envvar")?,
         oauth_client_secret: env::var("OAUTH_CLIENT_SECRET")
             .context("get `OAUTH_CLIENT_SECRET` envvar")?,
+        spurious_failures: SpuriousFailures::load(
+            env::var("SPURIOUS_FAILURES_PATH")
+                .ok()
+                .as_deref()
+                .map(Path::new),
+        )?,
+        repos: api::repos::load(env::var("REPOS_PATH").ok().as_deref().map(Path::new))?,
     };
 
     let db = db::migrate(PathBuf::from(config.db_path.clone()));
@@ -296,6 +576,9 @@ async fn main() -> color_eyre::Result<()> {
         // queue page
         .route("/queue", get(pages::queue::queue_page))
         .route("/queue/ws", any(pages::queue::queue_ws))
+        .route("/queue/{owner}/{repo}", get(pages::queue::repo_queue_page))
+        // scraper health
+        .route("/status", get(pages::status::status_page))
         // rest
         .with_state(Arc::new(AppState::new(db, config.clone())))
         .nest_service("/assets/", ServeDir::new(config.assets_dir.clone()));
@@ -1,10 +1,10 @@
 use std::{fmt::Display, ops::Deref};
 
 use jiff::{SignedDuration, Timestamp};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use url::Url;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Repo {
     pub owner: String,
     pub name: String,
@@ -16,10 +16,42 @@ impl Display for Repo {
     }
 }
 
+/// Where to get the merge queue of a repo from
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BorsBackend {
+    /// The original bors, scraped from its queue page
+    Homu { queue_url: Url },
+    /// rust-lang/bors, through its JSON API
+    NewBors { api_url: Url, repo_name: String },
+}
+
+/// Whether bors is merging PRs right now
+#[derive(Clone, Debug, Default)]
+pub enum QueueState {
+    #[default]
+    Open,
+    /// Only PRs with at least this priority get merged
+    TreeClosed {
+        priority: u64,
+        reason: Option<String>,
+        /// Usually the comment that closed the tree
+        source: Option<Url>,
+    },
+    /// Nothing gets merged, for example during a release
+    Paused { reason: Option<String> },
+}
+
+impl QueueState {
+    pub fn accepting_merges(&self) -> bool {
+        matches!(self, QueueState::Open)
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct RepoInfo {
     pub repo: Repo,
-    pub bors_queue_url: Option<Url>,
+    pub bors: Option<BorsBackend>,
 }
 
 impl Deref for RepoInfo {
@@ -30,20 +62,73 @@ impl Deref for RepoInfo {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FcpDisposition {
+    Merge,
+    Close,
+    Postpone,
+    Other(String),
+}
+
+impl Display for FcpDisposition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FcpDisposition::Merge => write!(f, "merge"),
+            FcpDisposition::Close => write!(f, "close"),
+            FcpDisposition::Postpone => write!(f, "postpone"),
+            FcpDisposition::Other(other) => write!(f, "{other}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FcpConcern {
+    pub name: String,
+    pub raised_by: Author,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct FcpStatus {
-    pub start: Timestamp,
+    /// None while the FCP is still being proposed
+    pub start: Option<Timestamp>,
+    pub disposition: FcpDisposition,
+    /// Reviewers who haven't checked their box yet
+    pub pending_reviewers: Vec<Author>,
+    /// Concerns that haven't been resolved yet
+    pub concerns: Vec<FcpConcern>,
 }
 
 impl FcpStatus {
-    pub fn ends_on(&self) -> Timestamp {
-        self.start
-            .checked_add(SignedDuration::from_hours(24 * 10))
-            .unwrap()
+    pub fn ends_on(&self) -> Option<Timestamp> {
+        self.start.map(|start| {
+            start
+                .checked_add(SignedDuration::from_hours(24 * 10))
+                .unwrap()
+        })
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug)]
+pub enum FcpTodoReason {
+    /// You're on the team and haven't checked your box yet
+    Checkbox,
+    /// You raised concerns that haven't been resolved yet
+    Concerns(Vec<String>),
+}
+
+/// An FCP that's waiting on the logged in user
+#[derive(Clone, Debug)]
+pub struct FcpTodo {
+    pub repo: Repo,
+    pub title: String,
+    pub number: u64,
+    pub link: Url,
+
+    pub reason: FcpTodoReason,
+    pub status: FcpStatus,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Author {
     pub name: String,
     pub id: u64,
@@ -51,12 +136,58 @@ pub struct Author {
     pub profile_url: Url,
 }
 
+impl Author {
+    /// For when all we know is someone's username, like from bors
+    pub fn from_username(name: String) -> Self {
+        let url = format!("https://github.com/{name}");
+        Self {
+            avatar_url: Url::parse(&format!("{url}.png")).unwrap(),
+            profile_url: Url::parse(&url).unwrap(),
+            name,
+            id: 0,
+        }
+    }
+}
+
+/// An open issue the way sorting needs it, from either the REST or the GraphQL API
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GitHubIssue {
+    pub number: u64,
+    pub title: String,
+    pub body: Option<String>,
+    pub url: Url,
+    pub author: Author,
+    pub assignees: Vec<Author>,
+    /// Just the names
+    pub labels: Vec<String>,
+    pub created_at: Timestamp,
+    pub updated_at: Timestamp,
+}
+
+/// An open PR, with what's on its issue
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GitHubPr {
+    pub issue: GitHubIssue,
+    pub head_sha: String,
+    /// People asked to review it through GitHub, rather than assigned
+    pub requested_reviewers: Vec<Author>,
+    pub draft: bool,
+    /// Behind its base or conflicting with it
+    pub conflicted: bool,
+    /// Whether it's in a private repo, only public PRs are shared between users
+    pub private: bool,
+}
+
 #[derive(Clone, Debug)]
 pub enum QueueStatus {
     Unknown,
     InQueue {
         position: usize,
     },
+    /// Not in a rollup yet, but likely to be picked for the next one
+    RollupCandidate {
+        position: usize,
+    },
     Running,
     InNextRollup {
         position: usize,
@@ -91,6 +222,19 @@ pub struct Pr {
     pub status: PrStatus,
 
     pub ci_status: CiStatus,
+    /// The checks on the head commit, only fetched for PRs that aren't queued
+    pub checks: Vec<Check>,
+    pub perf: Option<PerfSummary>,
+    /// Set when CI is bad and we found the failing job
+    pub ci_failure: Option<CiFailure>,
+    /// Set for authors whose PR probably made its rollup fail
+    pub broken_rollup: Option<BrokenRollup>,
+    /// Filled in right before showing the PR: sources its state comes from that are out of date
+    pub stale_sources: Vec<SourceHealth>,
+    /// Whether it came from scraping someone's PRs, rather than from e.g. the queue of a repo
+    pub scraped: bool,
+    /// When bors last failed to merge it, only looked up for the queue of a repo
+    pub failed_at: Option<Timestamp>,
 
     pub created: Timestamp,
 }
@@ -99,6 +243,125 @@ impl Pr {
     pub fn is_rollup_pr(&self) -> bool {
         self.title.starts_with("Rollup of") && self.title.ends_with("pull requests")
     }
+
+    /// Whether what we show about the PR comes from this source
+    pub fn depends_on(&self, health: &SourceHealth) -> bool {
+        if health.repo.as_ref().is_some_and(|repo| *repo != self.repo) {
+            return false;
+        }
+
+        let wait_reason = match &self.status {
+            PrStatus::Waiting { wait_reason } => Some(wait_reason),
+            _ => None,
+        };
+
+        match health.source {
+            // a scrape that never finished is the one that's still producing the cards
+            Source::GitHub => self.scraped && health.last_success.is_some(),
+            Source::Bors => matches!(self.status, PrStatus::Queued(_)),
+            Source::Crater => matches!(wait_reason, Some(WaitingReason::CraterRun(_))),
+            Source::Perf => matches!(wait_reason, Some(WaitingReason::PerfRun(_))),
+            Source::Rfcbot => matches!(wait_reason, Some(WaitingReason::Fcp(_))),
+        }
+    }
+
+    /// Whether something is running that can finish without the PR itself being updated
+    pub fn in_progress(&self) -> bool {
+        matches!(self.ci_status, CiStatus::Running)
+            || matches!(
+                &self.status,
+                PrStatus::Waiting {
+                    wait_reason: WaitingReason::TryBuild(try_build)
+                } if !try_build.is_done()
+            )
+    }
+}
+
+/// Where the dashboard gets its data from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Source {
+    Bors,
+    Crater,
+    Rfcbot,
+    Perf,
+    GitHub,
+}
+
+impl Source {
+    /// Somewhat longer than a few reloads, so one slow reload doesn't count
+    fn stale_after(&self) -> SignedDuration {
+        match self {
+            Source::Crater => SignedDuration::from_mins(30),
+            Source::Bors | Source::Rfcbot | Source::Perf | Source::GitHub => {
+                SignedDuration::from_mins(10)
+            }
+        }
+    }
+}
+
+impl Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Bors => write!(f, "bors queue"),
+            Source::Crater => write!(f, "crater queue"),
+            Source::Rfcbot => write!(f, "rfcbot FCPs"),
+            Source::Perf => write!(f, "perf queue"),
+            Source::GitHub => write!(f, "GitHub PRs"),
+        }
+    }
+}
+
+/// How loading a source went recently
+#[derive(Clone, Debug)]
+pub struct SourceHealth {
+    pub source: Source,
+    /// None for sources that cover all repos
+    pub repo: Option<Repo>,
+    pub last_success: Option<Timestamp>,
+    /// Cleared once loading succeeds again
+    pub last_error: Option<String>,
+    /// How many things the source told us about, when we know
+    pub items: Option<usize>,
+    /// Problems that didn't stop it from loading
+    pub warnings: Vec<String>,
+}
+
+impl SourceHealth {
+    pub fn is_stale(&self) -> bool {
+        self.last_success.is_none_or(|last_success| {
+            Timestamp::now().duration_since(last_success) > self.source.stale_after()
+        })
+    }
+
+    /// Whether the data from this source is old or the last attempt to load it failed
+    pub fn has_problem(&self) -> bool {
+        self.is_stale() || self.last_error.is_some()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CiFailure {
+    pub job_name: String,
+    pub job_url: Option<Url>,
+    /// The interesting part of the log
+    pub excerpt: Option<String>,
+    pub spurious: Option<SpuriousFailure>,
+}
+
+/// A CI failure that matches a known spurious one, so `@bors retry` is appropriate
+#[derive(Clone, Debug)]
+pub struct SpuriousFailure {
+    pub issue: u64,
+    pub issue_url: Url,
+}
+
+#[derive(Clone, Debug)]
+pub struct BrokenRollup {
+    pub rollup_number: u64,
+    pub log_url: Option<Url>,
+    pub job_name: Option<String>,
+    /// Whether this PR is the one the failure points at most
+    pub most_likely: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -111,10 +374,53 @@ pub enum CiStatus {
     Draft,
 }
 
+impl CiStatus {
+    /// Any failing check makes CI bad, otherwise any pending check means it's still running
+    pub fn from_checks(checks: &[Check]) -> Self {
+        if checks.is_empty() {
+            CiStatus::Unknown
+        } else if checks
+            .iter()
+            .any(|i| i.conclusion == CheckConclusion::Failure)
+        {
+            CiStatus::Bad
+        } else if checks
+            .iter()
+            .any(|i| i.conclusion == CheckConclusion::Pending)
+        {
+            CiStatus::Running
+        } else {
+            CiStatus::Good
+        }
+    }
+}
+
+/// A check run or commit status on a commit
+#[derive(Clone, Debug)]
+pub struct Check {
+    pub name: String,
+    pub conclusion: CheckConclusion,
+    /// How long it took, or for pending checks how long it's been running
+    pub duration: Option<SignedDuration>,
+    pub url: Option<Url>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckConclusion {
+    Pending,
+    Success,
+    Failure,
+    /// Skipped, neutral and the like, which don't say anything about the PR
+    Skipped,
+}
+
 #[derive(Clone, Debug)]
 pub enum PrStatus {
     /// Ready for yourself to work on
-    Ready {},
+    Ready {
+        /// Set when the PR is ready because its try build finished
+        try_build: Option<TryBuildStatus>,
+    },
     /// Ready for review work
     Review {
         other_reviewers: Vec<Author>,
@@ -142,16 +448,96 @@ pub enum RollupSetting {
 pub struct QueuedInfo {
     pub approvers: Vec<Author>,
     pub rollup_setting: RollupSetting,
+    pub priority: u64,
+    /// Whether bors thinks the PR can still be merged
+    pub mergeable: bool,
     pub queue_status: QueueStatus,
+    /// Based on how long test runs took recently
+    pub merge_eta: Option<SignedDuration>,
     pub url: Option<Url>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CraterStatus {
     Unknown,
-    Queued { num_before: usize },
-    Running { expected_end: Timestamp },
+    Queued {
+        num_before: usize,
+        expected_start: Option<Timestamp>,
+    },
+    Running {
+        expected_end: Option<Timestamp>,
+        /// Percentage of crates done
+        progress: Option<u8>,
+    },
     GeneratingReport,
+    Completed {
+        report_url: Url,
+        regressed: usize,
+        fixed: usize,
+        spurious: usize,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PerfStatus {
+    Unknown,
+    Queued { num_before: usize },
+    Benchmarking,
+    Finished { comparison_url: Url },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PerfChanges {
+    pub count: usize,
+    /// Smallest and largest change, in percent
+    pub range: Option<(f64, f64)>,
+}
+
+#[derive(Clone, Debug)]
+pub enum PerfVerdict {
+    Regressions,
+    Improvements,
+    Mixed,
+    Neutral,
+}
+
+/// The results of a perf run, as summarized by rust-timer
+#[derive(Clone, Debug)]
+pub struct PerfSummary {
+    pub comparison_url: Url,
+    /// Instruction count regressions, primary and secondary benchmarks combined
+    pub regressions: PerfChanges,
+    /// Instruction count improvements, primary and secondary benchmarks combined
+    pub improvements: PerfChanges,
+
+    pub instructions: PerfVerdict,
+    pub cycles: PerfVerdict,
+    pub max_rss: PerfVerdict,
+
+    /// Labeled `perf-regression`, but not `perf-regression-triaged`
+    pub needs_triage: bool,
+}
+
+#[derive(Clone, Debug)]
+pub enum TryBuildState {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Clone, Debug)]
+pub struct TryBuildStatus {
+    /// The merge commit bors is testing
+    pub commit: String,
+    pub started: Timestamp,
+    pub state: TryBuildState,
+    pub url: Option<Url>,
+}
+
+impl TryBuildStatus {
+    pub fn is_done(&self) -> bool {
+        !matches!(self.state, TryBuildState::Running)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -163,11 +549,12 @@ pub enum WaitingReason {
     /// It's your PR, waiting for the reviewer
     Review,
 
-    Fcp(FcpStatus),
+    /// None if rfcbot doesn't know about this FCP
+    Fcp(Option<FcpStatus>),
     CraterRun(CraterStatus),
 
-    TryBuild(),
-    PerfRun(),
+    TryBuild(TryBuildStatus),
+    PerfRun(PerfStatus),
 
     /// weird
     Unknown,
@@ -3,7 +3,6 @@ use std::{collections::HashMap, sync::Arc};
 use crate::{
     db::{MacroRoot, User},
     login_cx::LoginContext,
-    model::{Repo, RepoInfo},
     pages::queue::page_template,
 };
 use axum::{
@@ -26,9 +25,8 @@ use reqwest::{Client, StatusCode};
 use rust_query::{FromExpr, optional};
 use time::OffsetDateTime;
 use tokio::{sync::Mutex, task::spawn_blocking};
-use url::Url;
 
-use crate::{AppState, db::OauthState};
+use crate::{AppState, api::client::GitHubClient, db::OauthState};
 
 pub struct LoginError;
 
@@ -215,6 +213,7 @@ impl FromRequestParts<Arc<AppState>> for ExtractLoginContext {
                         current_username: user.login.clone(),
                         refresh_rate_seconds: 2 * 60,
                         sequence_number: 0,
+                        refresh_state: String::new(),
                     });
 
                     let data: User!(current_username) = txn.query_one(FromExpr::from_expr(user));
@@ -226,17 +225,11 @@ impl FromRequestParts<Arc<AppState>> for ExtractLoginContext {
         .unwrap();
 
         Ok(Self(Some(Arc::new(LoginContext {
-            octocrab,
+            github: GitHubClient::new(octocrab, user.login.clone(), state.rate_limits.clone()),
             current_username: Mutex::new(current_username),
             base_username: user.login,
 
-            repos: vec![RepoInfo {
-                repo: Repo {
-                    owner: "rust-lang".to_string(),
-                    name: "rust".to_string(),
-                },
-                bors_queue_url: Some(Url::parse("https://bors.rust-lang.org/queue/rust").unwrap()),
-            }],
+            repos: state.config.repos.clone(),
             state: state.clone(),
         }))))
     }
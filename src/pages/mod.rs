@@ -1,3 +1,4 @@
+use jiff::Timestamp;
 use serde::{Deserialize, Serialize};
 
 use crate::model::Author;
@@ -5,6 +6,7 @@ use crate::model::Author;
 pub mod auth;
 pub mod home;
 pub mod queue;
+pub mod status;
 
 #[derive(Deserialize)]
 #[serde(tag = "key")]
@@ -18,8 +20,20 @@ pub enum QueuePageWebsocketMessageRx {
 #[derive(Serialize)]
 #[serde(tag = "key")]
 pub enum QueuePageWebsocketMessageTx {
-    UpdatePage { main_contents: String },
-    SetUsername { new_name: String },
-    UsernameSuggestions { suggestions: Vec<Author> },
+    UpdatePage {
+        main_contents: String,
+        /// The summary in the nav bar
+        source_health: String,
+    },
+    SetUsername {
+        new_name: String,
+    },
+    UsernameSuggestions {
+        suggestions: Vec<Author>,
+    },
     UsernameNotValid,
+    /// Refreshes slowed down to stay within the GitHub API budget
+    RateLimited {
+        next_refresh: Timestamp,
+    },
 }
@@ -1,15 +1,16 @@
-use std::{collections::BTreeMap, iter, sync::Arc};
+use std::{cmp::Reverse, collections::BTreeMap, fmt::Display, iter, sync::Arc};
 
 use axum::{
     extract::{
-        WebSocketUpgrade,
+        Path, WebSocketUpgrade,
         ws::{Message, Utf8Bytes, WebSocket},
     },
+    http::StatusCode,
     response::{IntoResponse, Redirect, Response},
 };
 use futures::stream::SplitSink;
 use futures_util::{sink::SinkExt, stream::StreamExt};
-use jiff::{Span, SpanRound, Timestamp, Unit};
+use jiff::{SignedDuration, Span, SpanRound, Timestamp, Unit};
 use maud::{DOCTYPE, Markup, PreEscaped, Render, html};
 use tokio::{
     spawn,
@@ -22,11 +23,14 @@ use url::Url;
 use crate::{
     REFRESH_RATE,
     api::github::username_suggestions,
-    get_and_update_state, get_state_instantly,
+    get_and_update_state, get_closed_queues, get_fcp_todos, get_source_health, get_state_instantly,
     login_cx::LoginContext,
+    mark_stale,
     model::{
-        Author, CiStatus, CraterStatus, Pr, PrStatus, QueueStatus, QueuedInfo, RollupSetting,
-        WaitingReason,
+        Author, BrokenRollup, Check, CheckConclusion, CiFailure, CiStatus, CraterStatus,
+        FcpConcern, FcpStatus, FcpTodo, FcpTodoReason, PerfChanges, PerfStatus, PerfSummary,
+        PerfVerdict, Pr, PrStatus, QueueState, QueueStatus, QueuedInfo, Repo, RollupSetting,
+        SourceHealth, SpuriousFailure, TryBuildState, TryBuildStatus, WaitingReason,
     },
     pages::{QueuePageWebsocketMessageRx, QueuePageWebsocketMessageTx, auth::ExtractLoginContext},
 };
@@ -44,6 +48,8 @@ const PROGRESS: PreEscaped<&str> = PreEscaped(
     r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 640 640" fill="currentColor"><!--!Font Awesome Free v7.0.0 by @fontawesome - https://fontawesome.com License - https://fontawesome.com/license/free Copyright 2025 Fonticons, Inc.--><path d="M320 64C461.4 64 576 178.6 576 320C576 461.4 461.4 576 320 576C178.6 576 64 461.4 64 320C64 178.6 178.6 64 320 64zM296 184L296 320C296 328 300 335.5 306.7 340L402.7 404C413.7 411.4 428.6 408.4 436 397.3C443.4 386.2 440.4 371.4 429.3 364L344 307.2L344 184C344 170.7 333.3 160 320 160C306.7 160 296 170.7 296 184z"/></svg>"#,
 );
 
+const PERF_STATUS_URL: &str = "https://perf.rust-lang.org/status.html";
+
 pub fn field(label: impl Render, value: impl Render) -> Markup {
     html! {
         div class="field" {
@@ -78,11 +84,16 @@ pub fn page_template(body: Markup) -> Markup {
 }
 
 async fn refresh_prs(config: Arc<LoginContext>, tx: Sender<QueuePageWebsocketMessageTx>) {
-    let prs = spawn(get_and_update_state(config.clone())).await.unwrap();
-    let page = queue_page_main(&prs);
+    let mut prs = spawn(get_and_update_state(config.clone())).await.unwrap();
+    let fcp_todos = get_fcp_todos(config.clone()).await;
+    let closed_queues = get_closed_queues(config.clone()).await;
+    let health = get_source_health(config.clone()).await;
+    mark_stale(&mut prs, &health);
+    let page = queue_page_main(&prs, &fcp_todos, &closed_queues);
 
     let msg = QueuePageWebsocketMessageTx::UpdatePage {
         main_contents: page.into_string(),
+        source_health: SourceHealthSummary(&health).render().into_string(),
     };
 
     tx.send(msg).await.unwrap();
@@ -170,12 +181,35 @@ async fn send_task(
     }
 }
 
-async fn refresh_loop_task(tx: Sender<QueuePageWebsocketMessageRx>) {
+async fn refresh_loop_task(
+    login_context: Arc<LoginContext>,
+    rtx: Sender<QueuePageWebsocketMessageRx>,
+    stx: Sender<QueuePageWebsocketMessageTx>,
+) {
     loop {
-        tx.send(QueuePageWebsocketMessageRx::UpdatePrs)
+        rtx.send(QueuePageWebsocketMessageRx::UpdatePrs)
             .await
             .unwrap();
-        sleep(REFRESH_RATE).await;
+
+        let next_refresh = login_context
+            .state
+            .rate_limits
+            .next_refresh(
+                &login_context.github,
+                SignedDuration::try_from(REFRESH_RATE).unwrap(),
+            )
+            .await;
+
+        if next_refresh.rate_limited {
+            stx.send(QueuePageWebsocketMessageTx::RateLimited {
+                next_refresh: next_refresh.at,
+            })
+            .await
+            .unwrap();
+        }
+
+        let wait = next_refresh.at.duration_since(Timestamp::now());
+        sleep(wait.try_into().unwrap_or(REFRESH_RATE)).await;
     }
 }
 
@@ -193,9 +227,9 @@ pub async fn queue_ws(
         let (rtx, rrx) = channel(5);
         let (stx, srx) = channel(5);
 
-        spawn(receive_task(config.clone(), stx, rrx));
+        spawn(receive_task(config.clone(), stx.clone(), rrx));
         spawn(send_task(srx, tx));
-        let refesh_task = spawn(refresh_loop_task(rtx.clone()));
+        let refesh_task = spawn(refresh_loop_task(config.clone(), rtx.clone(), stx));
 
         while let Some(msg) = rx.next().await {
             let msg = match msg {
@@ -227,7 +261,11 @@ pub async fn queue_page(ExtractLoginContext(config): ExtractLoginContext) -> Res
         return Redirect::to("/").into_response();
     };
 
-    let prs = get_state_instantly(config.clone()).await;
+    let mut prs = get_state_instantly(config.clone()).await;
+    let fcp_todos = get_fcp_todos(config.clone()).await;
+    let closed_queues = get_closed_queues(config.clone()).await;
+    let health = get_source_health(config.clone()).await;
+    mark_stale(&mut prs, &health);
 
     let ws_url = format!(
         "{}/queue/ws",
@@ -240,6 +278,8 @@ pub async fn queue_page(ExtractLoginContext(config): ExtractLoginContext) -> Res
                 span id="refresh-time" {"getting PR data..."}
             }
 
+            (SourceHealthSummary(&health))
+
             div class="divider" {}
 
             form id="change-username" autocomplete="off" {
@@ -254,6 +294,14 @@ pub async fn queue_page(ExtractLoginContext(config): ExtractLoginContext) -> Res
             }
             button r#type="button" id="change-username-reset" {"Reset"}
 
+            div class="repo-queues" {
+                @for repo in config.repos.iter().filter(|i| i.bors.is_some()) {
+                    a href=(format!("/queue/{}/{}", repo.owner, repo.name)) {
+                        "queue of " (repo.repo)
+                    }
+                }
+            }
+
             div class="logout" {
                 a href="/logout" {
                     "logout"
@@ -261,7 +309,7 @@ pub async fn queue_page(ExtractLoginContext(config): ExtractLoginContext) -> Res
             }
         }
 
-        (queue_page_main(&prs))
+        (queue_page_main(&prs, &fcp_todos, &closed_queues))
 
         script {
             (PreEscaped(format!(r#"
@@ -274,19 +322,145 @@ pub async fn queue_page(ExtractLoginContext(config): ExtractLoginContext) -> Res
     .into_response()
 }
 
-fn queue_page_main(prs: &[Pr]) -> Markup {
+/// Everything in the bors queue of a repo, not just the viewer's PRs
+pub async fn repo_queue_page(
+    ExtractLoginContext(config): ExtractLoginContext,
+    Path((owner, name)): Path<(String, String)>,
+) -> Response {
+    let Some(config) = config else {
+        return Redirect::to("/").into_response();
+    };
+
+    let Some(repo) = config
+        .repos
+        .iter()
+        .find(|i| i.owner == owner && i.name == name)
+    else {
+        return (
+            StatusCode::NOT_FOUND,
+            page_template(html! {
+                main {
+                    h1 { "not tracking " (owner) "/" (name) }
+                }
+            }),
+        )
+            .into_response();
+    };
+
+    let mut prs = Vec::clone(&*config.state.repo_queue(repo.clone(), config.clone()).await);
+    let health = get_source_health(config.clone()).await;
+    mark_stale(&mut prs, &health);
+    let queue_state = config.state.bors_info(repo.clone()).await.state.clone();
+    let viewer = config.username().await;
+
+    page_template(html! {
+        nav {
+            div class="backend-status" {
+                span { "merge queue of " (repo.repo) }
+            }
+
+            div class="divider" {}
+
+            div class="logout" {
+                a href="/queue" {
+                    "back to my queue"
+                }
+            }
+        }
+
+        main {
+            @if !queue_state.accepting_merges() {
+                (queue_banner(&repo.repo, &queue_state))
+            }
+            (render_pr_box(QueuedPrBox(&prs, Some(&viewer))))
+            (render_pr_box(FailedPrBox(&prs, &viewer)))
+        }
+    })
+    .into_response()
+}
+
+fn queue_page_main(
+    prs: &[Pr],
+    fcp_todos: &[FcpTodo],
+    closed_queues: &[(Repo, QueueState)],
+) -> Markup {
     html! {
         main id="main" {
             (render_pr_box(ReadyPrBox(prs)))
             (render_pr_box(ReviewPrBox(prs)))
+            (render_pr_box(FcpCheckboxPrBox(fcp_todos)))
             (render_pr_box(BlockedPrBox(prs)))
-            (render_pr_box(QueuedPrBox(prs)))
+            @for (repo, state) in closed_queues {
+                (queue_banner(repo, state))
+            }
+            (render_pr_box(QueuedPrBox(prs, None)))
             (render_pr_box(SubscribedPrBox(prs)))
             (render_pr_box(DraftPrBox(prs)))
         }
     }
 }
 
+/// Links to the status page, which sources have problems is only in the title to keep it short
+pub struct SourceHealthSummary<'a>(pub &'a [SourceHealth]);
+
+impl Render for SourceHealthSummary<'_> {
+    fn render(&self) -> Markup {
+        let problems = self
+            .0
+            .iter()
+            .filter(|i| i.has_problem())
+            .map(|i| match &i.repo {
+                Some(repo) => format!("{} for {repo}", i.source),
+                None => i.source.to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        html! {
+            div id="source-health" class="source-health" {
+                @if problems.is_empty() {
+                    a href="/status" { "all sources ok" }
+                } @else {
+                    a class="problem" href="/status" title=(problems.join(", ")) {
+                        (WARN) " " (problems.len()) " "
+                        @if problems.len() == 1 { "source" } @else { "sources" }
+                        " out of date"
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Tells people why their approved PRs aren't moving
+fn queue_banner(repo: &Repo, state: &QueueState) -> Markup {
+    html! {
+        div class="queue-banner" {
+            (WARN)
+            span {
+                (repo) ": "
+                @match state {
+                    QueueState::Open => "open",
+                    QueueState::TreeClosed { priority, reason, source } => {
+                        "tree closed for p < " (priority)
+                        @if let Some(reason) = reason {
+                            ": " (reason)
+                        }
+                        @if let Some(source) = source {
+                            " (" a href=(source) { "see this comment" } ")"
+                        }
+                    }
+                    QueueState::Paused { reason } => {
+                        "bors is paused"
+                        @if let Some(reason) = reason {
+                            ": " (reason)
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 trait PrBox {
     type SortKey: Ord + Copy;
 
@@ -305,7 +479,7 @@ impl<'a> PrBox for ReadyPrBox<'a> {
 
     fn render(&self, res: &mut Vec<(Markup, &'a Timestamp)>) {
         for i in self.0 {
-            let PrStatus::Ready {} = i.status else {
+            let PrStatus::Ready { try_build } = &i.status else {
                 continue;
             };
 
@@ -313,7 +487,10 @@ impl<'a> PrBox for ReadyPrBox<'a> {
                 pr_skeleton(
                     i,
                     i.reviewers.iter().map(Field::Reviewer),
-                    vec![Badge::CiStatus(&i.ci_status)],
+                    try_build
+                        .iter()
+                        .map(Badge::TryBuild)
+                        .chain([Badge::CiStatus(&i.ci_status)]),
                 ),
                 &i.created,
             ));
@@ -349,6 +526,32 @@ impl<'a> PrBox for ReviewPrBox<'a> {
     }
 }
 
+struct FcpCheckboxPrBox<'a>(&'a [FcpTodo]);
+
+impl<'a> PrBox for FcpCheckboxPrBox<'a> {
+    type SortKey = (&'a Repo, u64);
+
+    fn title(&self) -> impl Render {
+        "Needs my FCP checkbox"
+    }
+
+    fn render(&self, res: &mut Vec<(Markup, Self::SortKey)>) {
+        for i in self.0 {
+            res.push((
+                skeleton(
+                    &i.link,
+                    &i.title,
+                    &i.repo,
+                    i.number,
+                    Field::fcp(&i.status),
+                    vec![Badge::FcpTodoReason(&i.reason), Badge::Fcp(&i.status)],
+                ),
+                (&i.repo, i.number),
+            ));
+        }
+    }
+}
+
 struct BlockedPrBox<'a>(&'a [Pr]);
 
 impl<'a> PrBox for BlockedPrBox<'a> {
@@ -369,7 +572,11 @@ impl<'a> PrBox for BlockedPrBox<'a> {
                     i,
                     iter::once(Field::Author(&i.author))
                         // TODO: only other reviewers?
-                        .chain(i.reviewers.iter().map(Field::Reviewer)),
+                        .chain(i.reviewers.iter().map(Field::Reviewer))
+                        .chain(match wait_reason {
+                            WaitingReason::Fcp(Some(fcp_status)) => Field::fcp(fcp_status),
+                            _ => Vec::new(),
+                        }),
                     vec![
                         Badge::WaitingReason(wait_reason),
                         Badge::CiStatus(&i.ci_status),
@@ -387,14 +594,48 @@ pub enum RollupPosition {
     Nth(usize),
 }
 
+/// The order homu merges in: the running PR, then mergeable ones by priority, then queue position
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy)]
+struct BorsOrder {
+    not_running: bool,
+    unmergeable: bool,
+    priority: Reverse<u64>,
+    position: usize,
+}
+
 #[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Copy)]
 enum QueuedSortKey<'a> {
     Rollup(RollupPosition),
-    Normal(usize),
+    Normal(BorsOrder),
     Other(&'a Timestamp),
 }
 
-struct QueuedPrBox<'a>(&'a [Pr]);
+/// Makes the viewer's own and reviewed PRs stand out between everyone else's
+fn highlight<'a>(
+    skeleton: Markup,
+    viewer: Option<&str>,
+    author: &Author,
+    reviewers: impl IntoIterator<Item = &'a Author>,
+) -> Markup {
+    let Some(viewer) = viewer else {
+        return skeleton;
+    };
+
+    let class = if author.name == viewer {
+        "highlight mine"
+    } else if reviewers.into_iter().any(|i| i.name == viewer) {
+        "highlight reviewed"
+    } else {
+        return skeleton;
+    };
+
+    html! {
+        div class=(class) { (skeleton) }
+    }
+}
+
+/// The second field is set on pages with everyone's PRs, to highlight the viewer's
+struct QueuedPrBox<'a>(&'a [Pr], Option<&'a str>);
 
 impl<'a> PrBox for QueuedPrBox<'a> {
     type SortKey = QueuedSortKey<'a>;
@@ -410,7 +651,10 @@ impl<'a> PrBox for QueuedPrBox<'a> {
             let PrStatus::Queued(QueuedInfo {
                 approvers,
                 rollup_setting,
+                priority,
+                mergeable,
                 queue_status,
+                merge_eta,
                 url,
             }) = &i.status
             else {
@@ -427,21 +671,39 @@ impl<'a> PrBox for QueuedPrBox<'a> {
                 i,
                 iter::once(Field::Author(&i.author)).chain(approvers.iter().map(Field::Approver)),
                 vec![
+                    Badge::Mergeable(*mergeable),
+                    Badge::Priority(*priority),
                     Badge::RollupSetting(rollup_setting),
                     Badge::QueueStatus(QueueStatusWithUrl {
                         status: queue_status,
                         url: url.as_ref(),
                     }),
+                    Badge::MergeEta(*merge_eta),
                     Badge::CiStatus(&i.ci_status),
                 ],
             );
+            let skeleton = highlight(
+                skeleton,
+                self.1,
+                &i.author,
+                approvers.iter().chain(&i.reviewers),
+            );
+
+            let bors_order = |not_running, position| {
+                QueuedSortKey::Normal(BorsOrder {
+                    not_running,
+                    unmergeable: !mergeable,
+                    priority: Reverse(*priority),
+                    position,
+                })
+            };
 
             match queue_status {
                 QueueStatus::Unknown => res.push((skeleton, QueuedSortKey::Other(&i.created))),
-                QueueStatus::InQueue { position } => {
-                    res.push((skeleton, QueuedSortKey::Normal(*position)))
+                QueueStatus::InQueue { position } | QueueStatus::RollupCandidate { position } => {
+                    res.push((skeleton, bors_order(true, *position)))
                 }
-                QueueStatus::Running => res.push((skeleton, QueuedSortKey::Normal(0))),
+                QueueStatus::Running => res.push((skeleton, bors_order(false, 0))),
                 QueueStatus::InNextRollup {
                     position,
                     pr_link,
@@ -493,6 +755,37 @@ impl<'a> PrBox for QueuedPrBox<'a> {
     }
 }
 
+/// PRs bors failed to merge, on the page with the whole queue of a repo. The latest failures
+/// come first, followed by the ones we don't know the time of.
+struct FailedPrBox<'a>(&'a [Pr], &'a str);
+
+impl<'a> PrBox for FailedPrBox<'a> {
+    type SortKey = Reverse<(Option<Timestamp>, &'a Timestamp)>;
+
+    fn title(&self) -> impl Render {
+        "Failed"
+    }
+
+    fn render(&self, res: &mut Vec<(Markup, Self::SortKey)>) {
+        for i in self.0 {
+            let PrStatus::Waiting { .. } = &i.status else {
+                continue;
+            };
+
+            let skeleton = pr_skeleton(
+                i,
+                iter::once(Field::Author(&i.author)).chain(i.reviewers.iter().map(Field::Reviewer)),
+                vec![Badge::CiStatus(&i.ci_status)],
+            );
+
+            res.push((
+                highlight(skeleton, Some(self.1), &i.author, &i.reviewers),
+                Reverse((i.failed_at, &i.created)),
+            ));
+        }
+    }
+}
+
 struct DraftPrBox<'a>(&'a [Pr]);
 
 impl<'a> PrBox for DraftPrBox<'a> {
@@ -580,6 +873,50 @@ impl Render for RollupSetting {
     }
 }
 
+/// A duration rounded to hours, like `2d 5h`
+struct HumanDuration(SignedDuration);
+impl Display for HumanDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = Span::try_from(self.0).unwrap();
+
+        let options = SpanRound::new()
+            .largest(Unit::Week)
+            .smallest(Unit::Hour)
+            .days_are_24_hours();
+
+        write!(f, "{:#}", span.round(options).unwrap())
+    }
+}
+
+/// A duration rounded to minutes, like `1h 12m`
+struct ShortDuration(SignedDuration);
+impl Display for ShortDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let span = Span::try_from(self.0).unwrap();
+
+        let options = SpanRound::new().largest(Unit::Hour).smallest(Unit::Minute);
+
+        write!(f, "{:#}", span.round(options).unwrap())
+    }
+}
+
+/// How long ago something happened, like `12m ago`
+pub struct Age(pub Option<Timestamp>);
+impl Render for Age {
+    fn render(&self) -> Markup {
+        let Some(at) = self.0 else {
+            return html! { "never" };
+        };
+
+        let ago = Timestamp::now().duration_since(at);
+        if ago < SignedDuration::from_mins(1) {
+            html! { "just now" }
+        } else {
+            html! { (ShortDuration(ago)) " ago" }
+        }
+    }
+}
+
 struct Ordinal(usize);
 impl Render for Ordinal {
     fn render(&self) -> Markup {
@@ -598,6 +935,7 @@ impl Render for QueueStatus {
     fn render(&self) -> Markup {
         match self {
             QueueStatus::InQueue { position } => html! {span {(Ordinal(*position)) " in queue"}},
+            QueueStatus::RollupCandidate { .. } => html! {span {"will likely be rolled up"}},
             QueueStatus::Running => html! {span {"running"}},
             QueueStatus::InRollup { nth_rollup: 0, .. } => html! {"in next rollup"},
             QueueStatus::InRollup { nth_rollup, .. } => {
@@ -637,8 +975,21 @@ impl<'a> Render for QueueStatusWithUrl<'a> {
 pub enum Badge<'a> {
     CiStatus(&'a CiStatus),
     WaitingReason(&'a WaitingReason),
+    Fcp(&'a FcpStatus),
+    FcpTodoReason(&'a FcpTodoReason),
+    TryBuild(&'a TryBuildStatus),
+    Perf(&'a PerfSummary),
+    BrokenRollup(&'a BrokenRollup),
+    Spurious(&'a SpuriousFailure),
     RollupSetting(&'a RollupSetting),
+    /// Bors priority, only shown when it's set
+    Priority(u64),
+    /// Only shown when bors says the PR can't be merged
+    Mergeable(bool),
     QueueStatus(QueueStatusWithUrl<'a>),
+    MergeEta(Option<SignedDuration>),
+    /// Part of what's shown comes from a source that hasn't loaded in a while
+    Stale(&'a SourceHealth),
 }
 
 impl Render for Badge<'_> {
@@ -659,12 +1010,71 @@ impl Render for Badge<'_> {
         match self {
             Badge::CiStatus(ci_status) => ci_status.render(),
             Badge::WaitingReason(waiting_reason) => maybe_badge(waiting_reason),
+            Badge::Fcp(fcp_status) => maybe_badge(fcp_status),
+            Badge::FcpTodoReason(reason) => maybe_badge(reason),
+            Badge::TryBuild(try_build) => maybe_badge(try_build),
+            Badge::Perf(perf) if perf.needs_triage => html! {
+                div class="status-badge warning" title="perf regression that hasn't been triaged yet" {
+                    (WARN) (perf)
+                }
+            },
+            Badge::Perf(perf) => maybe_badge(perf),
+            Badge::BrokenRollup(broken) => html! {
+                div class="status-badge warning" title=(broken_rollup_title(broken)) {
+                    (WARN)
+                    @if let Some(log_url) = &broken.log_url {
+                        a href=(log_url) target="_blank" rel="noopener noreferrer" {
+                            "your PR may have broken rollup #" (broken.rollup_number)
+                        }
+                    } @else {
+                        "your PR may have broken rollup #" (broken.rollup_number)
+                    }
+                }
+            },
+            Badge::Spurious(spurious) => html! {
+                div class="status-badge" title="matches a known spurious failure, `@bors retry` is probably enough" {
+                    a href=(spurious.issue_url) target="_blank" rel="noopener noreferrer" {
+                        "likely spurious (#" (spurious.issue) ")"
+                    }
+                }
+            },
             Badge::RollupSetting(rollup_setting) => maybe_badge(rollup_setting),
+            Badge::Priority(0) => html! {},
+            Badge::Priority(priority) => maybe_badge(format!("p={priority}")),
+            Badge::Mergeable(true) => html! {},
+            Badge::Mergeable(false) => html! {
+                div class="status-badge warning" title="bors can't merge this PR, it probably has conflicts" {
+                    (WARN) "unmergeable in queue"
+                }
+            },
             Badge::QueueStatus(queue_status) => maybe_badge(queue_status),
+            Badge::MergeEta(None) => html! {},
+            Badge::MergeEta(Some(eta)) => html! {
+                div class="status-badge" title="from the median test run, counting the runs that fail" {
+                    "~" (HumanDuration(*eta)) " to merge (p50)"
+                }
+            },
+            Badge::Stale(health) => html! {
+                div class="status-badge warning" title=(health.last_error.as_deref().unwrap_or("couldn't load it recently")) {
+                    (WARN)
+                    a href="/status" {
+                        (health.source) " from " (Age(health.last_success))
+                    }
+                }
+            },
         }
     }
 }
 
+fn broken_rollup_title(broken: &BrokenRollup) -> String {
+    let job = broken.job_name.as_deref().unwrap_or("CI");
+    if broken.most_likely {
+        format!("the files this PR changed show up most in the failure of {job}")
+    } else {
+        format!("some files this PR changed show up in the failure of {job}")
+    }
+}
+
 impl Render for Author {
     fn render(&self) -> Markup {
         html! {
@@ -699,52 +1109,190 @@ impl Render for CiStatus {
     }
 }
 
+impl Render for FcpStatus {
+    fn render(&self) -> Markup {
+        let disposition = &self.disposition;
+
+        let Some(ends_on) = self.ends_on() else {
+            let boxes = self.pending_reviewers.len();
+            let concerns = self.concerns.len();
+
+            return html! {
+                span {
+                    (format!("FCP to {disposition} proposed, waiting on {boxes} "))
+                    @if boxes == 1 { "box" } @else { "boxes" }
+                    @if concerns > 0 {
+                        (format!(" and {concerns} "))
+                        @if concerns == 1 { "concern" } @else { "concerns" }
+                    }
+                }
+            };
+        };
+
+        let duration = ends_on.duration_since(Timestamp::now());
+        if duration.is_negative() {
+            return html! {
+                span {(format!("FCP to {disposition} complete"))}
+            };
+        }
+
+        html! {
+            span {(format!("in 10-day FCP to {disposition}, ends in {}", HumanDuration(duration)))}
+        }
+    }
+}
+
+impl Render for PerfVerdict {
+    fn render(&self) -> Markup {
+        match self {
+            PerfVerdict::Regressions => html! {"regressions"},
+            PerfVerdict::Improvements => html! {"improvements"},
+            PerfVerdict::Mixed => html! {"mixed"},
+            PerfVerdict::Neutral => html! {"neutral"},
+        }
+    }
+}
+
+impl Render for PerfSummary {
+    fn render(&self) -> Markup {
+        fn changes(symbol: &str, changes: &PerfChanges) -> String {
+            match changes.range {
+                Some((low, high)) => format!("{symbol}{} [{low:.1}%, {high:.1}%]", changes.count),
+                None => format!("{symbol}{}", changes.count),
+            }
+        }
+
+        let title = format!(
+            "instructions: {}, cycles: {}, max-rss: {}",
+            self.instructions.render().into_string(),
+            self.cycles.render().into_string(),
+            self.max_rss.render().into_string(),
+        );
+
+        html! {
+            a href=(self.comparison_url) title=(title) target="_blank" rel="noopener noreferrer" {
+                "perf "
+                @match (self.regressions.count, self.improvements.count) {
+                    (0, 0) => "neutral",
+                    (_, 0) => (changes("❌", &self.regressions)),
+                    (0, _) => (changes("✅", &self.improvements)),
+                    _ => {
+                        (changes("❌", &self.regressions)) " " (changes("✅", &self.improvements))
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Render for TryBuildStatus {
+    fn render(&self) -> Markup {
+        let commit = &self.commit[..self.commit.len().min(7)];
+
+        let text = match self.state {
+            TryBuildState::Running => {
+                let duration = Timestamp::now().duration_since(self.started);
+                format!("try build {commit} running for {}", ShortDuration(duration))
+            }
+            TryBuildState::Succeeded => format!("try build {commit} succeeded"),
+            TryBuildState::Failed => format!("try build {commit} failed"),
+        };
+
+        match &self.url {
+            Some(url) => html! {
+                a href=(url) target="_blank" rel="noopener noreferrer" { (text) }
+            },
+            None => html! {
+                span { (text) }
+            },
+        }
+    }
+}
+
 impl Render for WaitingReason {
     fn render(&self) -> Markup {
         match self {
             WaitingReason::Blocked => html! {
                 "blocked"
             },
-            WaitingReason::TryBuild() => html! {},
-            WaitingReason::PerfRun() => html! {},
+            WaitingReason::TryBuild(try_build) => try_build.render(),
+            WaitingReason::PerfRun(perf_status) => match perf_status {
+                PerfStatus::Unknown => html! {
+                    "waiting on perf, context unknown (bug?)"
+                },
+                PerfStatus::Queued { num_before } => html! {
+                    a href=(PERF_STATUS_URL) target="_blank" rel="noopener noreferrer" {
+                        (format!("perf queued ({num_before} before)"))
+                    }
+                },
+                PerfStatus::Benchmarking => html! {
+                    a href=(PERF_STATUS_URL) target="_blank" rel="noopener noreferrer" {
+                        "benchmarking"
+                    }
+                },
+                PerfStatus::Finished { comparison_url } => html! {
+                    a href=(comparison_url) target="_blank" rel="noopener noreferrer" {
+                        "perf results ready"
+                    }
+                },
+            },
             WaitingReason::CraterRun(crater_status) => match crater_status {
                 CraterStatus::Unknown => html! {
                     "running crater, context unknown (bug?)"
                 },
-                CraterStatus::Queued { num_before } => html! {
+                CraterStatus::Queued {
+                    num_before,
+                    expected_start: Some(expected_start),
+                } if *expected_start > Timestamp::now() => html! {
+                    (format!(
+                        "in crater queue ({num_before} queued before this), starts in ~{}",
+                        HumanDuration(expected_start.duration_since(Timestamp::now()))
+                    ))
+                },
+                CraterStatus::Queued { num_before, .. } => html! {
                     (format!("in crater queue ({} queued before this)", num_before))
                 },
                 CraterStatus::GeneratingReport => html! {
                     "generating crater report"
                 },
-                CraterStatus::Running { expected_end } => {
-                    let duration = expected_end.duration_since(Timestamp::now());
-                    let span = Span::try_from(duration).unwrap();
-
-                    let options = SpanRound::new()
-                        .largest(Unit::Week)
-                        .smallest(Unit::Hour)
-                        .days_are_24_hours();
-
-                    html! {
-                        (format!("crater experiment done in {:#}", span.round(options).unwrap()))
+                CraterStatus::Completed {
+                    report_url,
+                    regressed,
+                    fixed,
+                    spurious,
+                } => html! {
+                    a href=(report_url) target="_blank" rel="noopener noreferrer" {
+                        @if *regressed > 0 {
+                            "crater done, needs triage: "
+                        } @else {
+                            "crater done: "
+                        }
+                        (format!("{regressed} regressed, {fixed} fixed, {spurious} spurious"))
+                    }
+                },
+                CraterStatus::Running {
+                    expected_end,
+                    progress,
+                } => {
+                    let progress = progress.map(|i| format!(" ({i}%)")).unwrap_or_default();
+
+                    match expected_end {
+                        Some(expected_end) if *expected_end > Timestamp::now() => html! {
+                            (format!(
+                                "crater experiment{progress} done in {}",
+                                HumanDuration(expected_end.duration_since(Timestamp::now()))
+                            ))
+                        },
+                        _ => html! {
+                            (format!("running crater experiment{progress}"))
+                        },
                     }
                 }
             },
-            WaitingReason::Fcp(fcp_status) => {
-                // TODO: FCP concerns
-                let duration = fcp_status.ends_on().duration_since(Timestamp::now());
-                let span = Span::try_from(duration).unwrap();
-
-                let options = SpanRound::new()
-                    .largest(Unit::Week)
-                    .smallest(Unit::Hour)
-                    .days_are_24_hours();
-
-                html! {
-                    span {(format!("FCP ends in {:#}", span.round(options).unwrap()))}
-                }
-            }
+            WaitingReason::Fcp(None) => html! {
+                "in FCP, context unknown (bug?)"
+            },
+            WaitingReason::Fcp(Some(fcp_status)) => fcp_status.render(),
             WaitingReason::Author => html! {
                 "Waiting for author"
             },
@@ -756,11 +1304,103 @@ impl Render for WaitingReason {
     }
 }
 
+impl Render for FcpTodoReason {
+    fn render(&self) -> Markup {
+        match self {
+            FcpTodoReason::Checkbox => html! {"check your box"},
+            FcpTodoReason::Concerns(concerns) if concerns.len() == 1 => {
+                html! {"your concern is open"}
+            }
+            FcpTodoReason::Concerns(concerns) => {
+                html! {(format!("{} of your concerns are open", concerns.len()))}
+            }
+        }
+    }
+}
+
 pub enum Field<'a> {
     Reviewer(&'a Author),
     Author(&'a Author),
     Approver(&'a Author),
     OtherReviewer(&'a Author),
+    UncheckedBoxes(&'a [Author]),
+    Concern(&'a FcpConcern),
+    CiFailure(&'a CiFailure),
+    FailingChecks(Vec<&'a Check>),
+    PendingChecks(Vec<&'a Check>),
+}
+
+impl<'a> Field<'a> {
+    /// The checks that are failing and those still running, if there are any
+    fn checks(checks: &'a [Check]) -> Vec<Self> {
+        let with = |conclusion| {
+            checks
+                .iter()
+                .filter(|i| i.conclusion == conclusion)
+                .collect::<Vec<_>>()
+        };
+        let failing = with(CheckConclusion::Failure);
+        let pending = with(CheckConclusion::Pending);
+
+        (!failing.is_empty())
+            .then_some(Field::FailingChecks(failing))
+            .into_iter()
+            .chain((!pending.is_empty()).then_some(Field::PendingChecks(pending)))
+            .collect()
+    }
+
+    /// Extra fields for PRs in FCP: who still has to check their box and the open concerns
+    fn fcp(fcp_status: &'a FcpStatus) -> Vec<Self> {
+        // once the FCP started nobody can check their box anymore
+        let unchecked = (fcp_status.start.is_none() && !fcp_status.pending_reviewers.is_empty())
+            .then_some(Field::UncheckedBoxes(&fcp_status.pending_reviewers));
+
+        unchecked
+            .into_iter()
+            .chain(fcp_status.concerns.iter().map(Field::Concern))
+            .collect()
+    }
+}
+
+struct Checks<'a>(&'a [&'a Check]);
+
+impl Render for Checks<'_> {
+    fn render(&self) -> Markup {
+        html! {
+            ul class="checks" {
+                @for check in self.0 {
+                    li {
+                        @if let Some(url) = &check.url {
+                            a href=(url) target="_blank" rel="noopener noreferrer" { (check.name) }
+                        } @else {
+                            (check.name)
+                        }
+                        @if let Some(duration) = check.duration {
+                            span class="duration" { " (" (ShortDuration(duration)) ")" }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+struct Avatars<'a>(&'a [Author]);
+
+impl Render for Avatars<'_> {
+    fn render(&self) -> Markup {
+        html! {
+            div class="avatars" {
+                @for author in self.0 {
+                    a href=(author.profile_url) title=(author.name)
+                        target="_blank" rel="noopener noreferrer"
+                    {
+                        img class="avatar" src=(author.avatar_url) alt=(format!("{}'s profile picture", author.name));
+                    }
+                }
+            }
+        }
+    }
 }
 
 impl Render for Field<'_> {
@@ -769,25 +1409,80 @@ impl Render for Field<'_> {
             Field::Reviewer(author) => field("Reviewer", author),
             Field::Author(author) => field("Author", author),
             Field::OtherReviewer(author) => field("Other reviewer", author),
-            // TODO: should be bors approver
             Field::Approver(author) => field("Approver", author),
+            Field::UncheckedBoxes(authors) => field("Unchecked boxes", Avatars(authors)),
+            Field::Concern(concern) => {
+                field(html! {"Concern " code {(concern.name)}}, &concern.raised_by)
+            }
+            Field::CiFailure(failure) => field(
+                "Failing job",
+                html! {
+                    div class="ci-failure" {
+                        @if let Some(job_url) = &failure.job_url {
+                            a href=(job_url) target="_blank" rel="noopener noreferrer" { (failure.job_name) }
+                        } @else {
+                            (failure.job_name)
+                        }
+                        @if let Some(excerpt) = &failure.excerpt {
+                            details {
+                                summary { "log excerpt" }
+                                pre { (excerpt) }
+                            }
+                        }
+                    }
+                },
+            ),
+            Field::FailingChecks(checks) => field("Failing checks", Checks(checks)),
+            Field::PendingChecks(checks) => field("Pending checks", Checks(checks)),
         }
     }
 }
 
 fn pr_skeleton<'a>(
-    pr: &Pr,
+    pr: &'a Pr,
+    fields: impl IntoIterator<Item = Field<'a>>,
+    badges: impl IntoIterator<Item = Badge<'a>>,
+) -> Markup {
+    skeleton(
+        &pr.link,
+        &pr.title,
+        &pr.repo,
+        pr.number,
+        fields
+            .into_iter()
+            .chain(Field::checks(&pr.checks))
+            .chain(pr.ci_failure.iter().map(Field::CiFailure)),
+        pr.broken_rollup
+            .iter()
+            .map(Badge::BrokenRollup)
+            .chain(
+                pr.ci_failure
+                    .iter()
+                    .filter_map(|i| i.spurious.as_ref())
+                    .map(Badge::Spurious),
+            )
+            .chain(pr.perf.iter().map(Badge::Perf))
+            .chain(badges)
+            .chain(pr.stale_sources.iter().map(Badge::Stale)),
+    )
+}
+
+fn skeleton<'a>(
+    link: &Url,
+    title: &str,
+    repo: &Repo,
+    number: u64,
     fields: impl IntoIterator<Item = Field<'a>>,
     badges: impl IntoIterator<Item = Badge<'a>>,
 ) -> Markup {
     html! {
         div class="pr" {
-            h2 class="title" { a target="_blank" rel="noopener noreferrer" href=(pr.link) {
-                (pr.title)
+            h2 class="title" { a target="_blank" rel="noopener noreferrer" href=(link) {
+                (title)
             }}
 
-            a class="pr-link" target="_blank" rel="noopener noreferrer" href=(pr.link) {
-                (pr.repo) "#" (pr.number)
+            a class="pr-link" target="_blank" rel="noopener noreferrer" href=(link) {
+                (repo) "#" (number)
             }
 
             div class="fields" {
@@ -0,0 +1,111 @@
+use axum::response::{IntoResponse, Redirect, Response};
+use maud::html;
+
+use crate::{
+    get_source_health,
+    pages::{
+        auth::ExtractLoginContext,
+        queue::{Age, page_template},
+    },
+};
+
+/// How each data source is doing, so people know why data is missing or old
+pub async fn status_page(ExtractLoginContext(config): ExtractLoginContext) -> Response {
+    let Some(config) = config else {
+        return Redirect::to("/").into_response();
+    };
+
+    let health = get_source_health(config.clone()).await;
+
+    // the rows bors queues had that we couldn't (fully) make sense of
+    let mut problems = Vec::new();
+    for repo in &config.repos {
+        if repo.bors.is_none() {
+            continue;
+        }
+        let queue = config.state.bors_info(repo.clone()).await;
+        let report = &queue.parse_report;
+        if !report.skipped_rows.is_empty() {
+            problems.push(("skipped", repo.repo.clone(), report.skipped_rows.clone()));
+        }
+        if !report.warnings.is_empty() {
+            problems.push(("partly read", repo.repo.clone(), report.warnings.clone()));
+        }
+    }
+
+    page_template(html! {
+        main class="status" {
+            h1 { "Data sources" }
+
+            table {
+                thead {
+                    tr {
+                        th { "source" }
+                        th { "repo" }
+                        th { "last success" }
+                        th { "items" }
+                        th { "last error" }
+                        th { "warnings" }
+                    }
+                }
+                tbody {
+                    @for source in &health {
+                        tr class=[source.has_problem().then_some("problem")] {
+                            td { (source.source) }
+                            td {
+                                @if let Some(repo) = &source.repo {
+                                    (repo)
+                                }
+                            }
+                            td { (Age(source.last_success)) }
+                            td {
+                                @if let Some(items) = source.items {
+                                    (items)
+                                }
+                            }
+                            td {
+                                @if let Some(error) = &source.last_error {
+                                    code { (error) }
+                                }
+                            }
+                            td {
+                                @for warning in &source.warnings {
+                                    div { (warning) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            @for (what, repo, rows) in &problems {
+                section {
+                    h2 { (what) " rows of the bors queue for " (repo) }
+                    table {
+                        thead {
+                            tr {
+                                th { "row" }
+                                th { "PR" }
+                                th { "reason" }
+                            }
+                        }
+                        tbody {
+                            @for row in rows {
+                                tr class="problem" {
+                                    td { (row.row) }
+                                    td {
+                                        @if let Some(number) = row.pr_number {
+                                            "#" (number)
+                                        }
+                                    }
+                                    td { (row.reason) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    })
+    .into_response()
+}
@@ -1,27 +1,80 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
-use octocrab::models::{
-    issues::Issue,
-    pulls::{MergeableState, PullRequest},
-};
+use color_eyre::eyre::Context;
+use jiff::{SignedDuration, Timestamp};
 
 use crate::{
-    api::bors::{BorsPr, BorsQueue, BorsStatus},
+    api::{
+        bors::{BorsPr, BorsQueue, BorsStatus, failed_at},
+        checks::head_checks,
+        ci_failure::{CiFailureVersion, get_ci_failure},
+        crater::get_crater_report,
+        github::get_prs,
+        merge_times::runs_ahead,
+        perf::get_perf_summary,
+        rollup::RollupQueue,
+        rollup_failure::find_culprits,
+        try_build::get_try_build,
+    },
     login_cx::LoginContext,
     model::{
-        Author, CiStatus, CraterStatus, Pr, PrStatus, QueueStatus, QueuedInfo, RepoInfo,
-        WaitingReason,
+        Author, BrokenRollup, Check, CiFailure, CiStatus, CraterStatus, GitHubIssue, GitHubPr,
+        PerfStatus, PerfSummary, Pr, PrStatus, QueueStatus, QueuedInfo, Repo, RepoInfo,
+        TryBuildStatus, WaitingReason,
     },
 };
 
-fn label(issue: &Issue, label: impl AsRef<str>) -> bool {
-    issue.labels.iter().any(|i| i.name == label.as_ref())
+fn label(issue: &GitHubIssue, label: impl AsRef<str>) -> bool {
+    issue.labels.iter().any(|i| i == label.as_ref())
+}
+
+/// Labels a PR gets from a perf run, either while it's going or for its results
+const PERF_LABELS: &[&str] = &[
+    "S-waiting-on-perf",
+    "perf-regression",
+    "perf-regression-triaged",
+];
+
+/// Only for PRs with a perf label, finding rust-timer's comment costs a request per PR
+async fn perf_summary(
+    login_context: &LoginContext,
+    repo: &RepoInfo,
+    issue: &GitHubIssue,
+) -> Option<PerfSummary> {
+    if !PERF_LABELS.iter().any(|i| label(issue, i)) {
+        return None;
+    }
+
+    let summary = login_context
+        .state
+        .perf_summaries
+        .get_or_reload(
+            &repo.repo,
+            issue.number,
+            issue.updated_at,
+            get_perf_summary(&login_context.github, &repo.repo, issue.number),
+        )
+        .await;
+
+    let mut summary = match summary {
+        Ok(i) => i?,
+        Err(e) => {
+            tracing::error!("perf summary error: {e}");
+            return None;
+        }
+    };
+
+    summary.needs_triage =
+        label(issue, "perf-regression") && !label(issue, "perf-regression-triaged");
+
+    Some(summary)
 }
 
 async fn sort_waiting(
     login_context: &LoginContext,
-    issue: &Issue,
-    _pr: &PullRequest,
+    repo: &RepoInfo,
+    issue: &GitHubIssue,
+    _pr: &GitHubPr,
     _bors_for_pr: Option<&BorsPr>,
 ) -> WaitingReason {
     if label(issue, "S-waiting-on-author") {
@@ -31,18 +84,55 @@ async fn sort_waiting(
     } else if label(issue, "S-waiting-on-review") {
         WaitingReason::Review
     } else if label(issue, "S-final-comment-period") || label(issue, "S-waiting-on-concerns") {
-        WaitingReason::Fcp(todo!())
+        let fcp_info = login_context.state.fcp_info.get().await;
+
+        let Some(fcp) = fcp_info.get(&(repo.repo.clone(), issue.number)) else {
+            tracing::warn!("no fcp info for {}#{}", repo.repo, issue.number);
+            return WaitingReason::Fcp(None);
+        };
+
+        WaitingReason::Fcp(Some(fcp.status()))
     } else if label(issue, "S-waiting-on-crater") {
         let crater_info = login_context.state.crater_info.get().await;
 
-        let Some(crater_status) = crater_info.get(&issue.number) else {
-            return WaitingReason::CraterRun(CraterStatus::Unknown);
-            // return Ok(Some(SharedStatus::Crater(CraterInfo {
-            // status: status.clone(),
-            // })));
-        };
+        if let Some(crater_status) = crater_info.get(&issue.number) {
+            return WaitingReason::CraterRun(crater_status.clone());
+        }
 
-        WaitingReason::CraterRun(crater_status.clone())
+        // not in the queue anymore, so maybe the experiment is done
+        let report = login_context
+            .state
+            .crater_reports
+            .get_or_reload(
+                &repo.repo,
+                issue.number,
+                issue.updated_at,
+                get_crater_report(&login_context.github, &repo.repo, issue.number),
+            )
+            .await;
+
+        match report {
+            Ok(Some(crater_status)) => WaitingReason::CraterRun(crater_status),
+            Ok(None) => WaitingReason::CraterRun(CraterStatus::Unknown),
+            Err(e) => {
+                tracing::error!("crater report error: {e}");
+                WaitingReason::CraterRun(CraterStatus::Unknown)
+            }
+        }
+    } else if label(issue, "S-waiting-on-perf") {
+        let perf_info = login_context.state.perf_info.get().await;
+
+        if let Some(perf_status) = perf_info.get(&issue.number) {
+            return WaitingReason::PerfRun(perf_status.clone());
+        }
+
+        // not in the queue anymore, so maybe the results are in
+        match perf_summary(login_context, repo, issue).await {
+            Some(summary) => WaitingReason::PerfRun(PerfStatus::Finished {
+                comparison_url: summary.comparison_url,
+            }),
+            None => WaitingReason::PerfRun(PerfStatus::Unknown),
+        }
     } else {
         tracing::error!(
             "no clue why we're waiting... {} {}",
@@ -53,96 +143,294 @@ async fn sort_waiting(
     }
 }
 
-async fn sort_queued(
-    login_context: &LoginContext,
-    repo: &RepoInfo,
-    issue: &Issue,
-    bors_for_pr: Option<&BorsPr>,
-) -> QueuedInfo {
-    let rollup_status = if let Some(bors) = bors_for_pr {
-        let mut rollup_status = QueueStatus::InQueue {
+/// bors only knows usernames (`r=a,b`), so use the full GitHub user where they're assigned
+fn bors_approvers(assignees: &[Author], approver: &str) -> Vec<Author> {
+    approver
+        .split(',')
+        .map(str::trim)
+        .filter(|i| !i.is_empty())
+        .map(|name| {
+            assignees
+                .iter()
+                .find(|i| i.name.eq_ignore_ascii_case(name))
+                .cloned()
+                .unwrap_or_else(|| Author::from_username(name.to_string()))
+        })
+        .collect()
+}
+
+/// Where a queued PR is in the bors queue, and which rollup it's part of
+fn queue_status(bors: &BorsPr, number: u64, rollup_queue: &RollupQueue) -> QueueStatus {
+    if bors.running {
+        return QueueStatus::Running;
+    }
+
+    for (idx, rollup) in rollup_queue.rollups.iter().enumerate() {
+        if !rollup.is_pending() {
+            continue;
+        }
+
+        if rollup.pr_numbers.contains(&number) {
+            return if rollup.running {
+                QueueStatus::InRunningRollup {
+                    pr_link: rollup.pr_link.clone(),
+                    pr_number: rollup.pr_number,
+                    rollup_size: rollup.pr_numbers.len(),
+                }
+            } else if idx == 0 {
+                QueueStatus::InNextRollup {
+                    position: rollup.position_in_queue,
+                    pr_link: rollup.pr_link.clone(),
+                    pr_number: rollup.pr_number,
+                    rollup_size: rollup.pr_numbers.len(),
+                }
+            } else {
+                QueueStatus::InRollup {
+                    nth_rollup: idx,
+                    pr_link: rollup.pr_link.clone(),
+                    pr_number: rollup.pr_number,
+                    rollup_size: rollup.pr_numbers.len(),
+                }
+            };
+        }
+    }
+
+    if rollup_queue.candidates.contains(&number) {
+        return QueueStatus::RollupCandidate {
             position: bors.position_in_queue,
         };
+    }
 
-        if bors.running {
-            rollup_status = QueueStatus::Running;
-        } else {
-            for (idx, rollup) in login_context
-                .state
-                .clone()
-                .rollup_info(repo.clone(), login_context.octocrab.clone())
-                .await
-                .rollups
-                .iter()
-                .enumerate()
-            {
-                if !matches!(
-                    rollup.status,
-                    BorsStatus::Pending | BorsStatus::Success | BorsStatus::Approved
-                ) {
-                    continue;
-                }
+    QueueStatus::InQueue {
+        position: bors.position_in_queue,
+    }
+}
 
-                if rollup.pr_numbers.contains(&issue.number) {
-                    rollup_status = if rollup.running {
-                        QueueStatus::InRunningRollup {
-                            pr_link: rollup.pr_link.clone(),
-                            pr_number: rollup.pr_number,
-                            rollup_size: rollup.pr_numbers.len(),
-                        }
-                    } else if idx == 0 {
-                        QueueStatus::InNextRollup {
-                            position: rollup.position_in_queue,
-                            pr_link: rollup.pr_link.clone(),
-                            pr_number: rollup.pr_number,
-                            rollup_size: rollup.pr_numbers.len(),
-                        }
-                    } else {
-                        QueueStatus::InRollup {
-                            nth_rollup: idx,
-                            pr_link: rollup.pr_link.clone(),
-                            pr_number: rollup.pr_number,
-                            rollup_size: rollup.pr_numbers.len(),
-                        }
-                    };
-
-                    break;
-                }
-            }
+/// Not finding one isn't cached, the checks may just not have been there yet
+async fn ci_failure(
+    login_context: &LoginContext,
+    repo: &RepoInfo,
+    pr: &GitHubPr,
+    checks: &[Check],
+) -> Option<CiFailure> {
+    let failure = login_context
+        .state
+        .ci_failures
+        .get_or_reload(
+            &repo.repo,
+            pr.issue.number,
+            CiFailureVersion::new(pr, checks),
+            get_ci_failure(
+                &login_context.github,
+                &login_context.state.config.spurious_failures,
+                &repo.repo,
+                pr.issue.number,
+                &pr.head_sha,
+            ),
+        )
+        .await;
+
+    match failure {
+        Ok(Some(i)) => Some(i),
+        Ok(None) => {
+            login_context
+                .state
+                .ci_failures
+                .remove(&repo.repo, pr.issue.number)
+                .await;
+            None
+        }
+        Err(e) => {
+            tracing::error!("ci failure error: {e}");
+            None
         }
+    }
+}
 
-        rollup_status
-    } else {
-        tracing::warn!("bors was none for {}#{}", repo.repo, issue.number);
-        QueueStatus::Unknown
+/// Only for authors, when their PR is in a failed rollup and the failure points at it
+async fn broken_rollup(
+    login_context: &LoginContext,
+    username: &str,
+    repo: &RepoInfo,
+    issue: &GitHubIssue,
+) -> Option<BrokenRollup> {
+    if issue.author.name != username {
+        return None;
+    }
+
+    let rollup_queue = login_context
+        .state
+        .clone()
+        .rollup_info(repo.clone(), login_context.github.clone())
+        .await;
+
+    let rollup = rollup_queue.rollups.iter().find(|r| {
+        matches!(r.status, BorsStatus::Failure | BorsStatus::Error)
+            && r.pr_numbers.contains(&issue.number)
+    })?;
+
+    let failed_rollup = login_context
+        .state
+        .rollup_failures
+        .get_or_reload(
+            &repo.repo,
+            rollup.pr_number,
+            rollup.updated_at,
+            find_culprits(
+                &login_context.github,
+                &login_context.state.config.spurious_failures,
+                &repo.repo,
+                rollup,
+            ),
+        )
+        .await;
+
+    let failed_rollup = match failed_rollup {
+        Ok(i) => i?,
+        Err(e) => {
+            tracing::error!("rollup culprits error: {e}");
+            return None;
+        }
     };
 
+    let rank = failed_rollup
+        .culprits
+        .iter()
+        .position(|(number, _)| *number == issue.number)?;
+
+    Some(BrokenRollup {
+        rollup_number: rollup.pr_number,
+        log_url: failed_rollup.log_url,
+        job_name: failed_rollup.job_name,
+        most_likely: rank == 0,
+    })
+}
+
+/// Counts the runs ahead of the PR, or of the rollup it's in
+async fn merge_eta(
+    login_context: &LoginContext,
+    repo: &Repo,
+    bors_for_repo: &BorsQueue,
+    rollup_queue: &RollupQueue,
+    bors: &BorsPr,
+) -> Option<SignedDuration> {
+    let position = rollup_queue
+        .rollups
+        .iter()
+        .find(|r| r.is_pending() && r.pr_numbers.contains(&bors.pr_number))
+        .map_or(bors.position_in_queue, |r| r.position_in_queue);
+
+    login_context.state.merge_times.lock().await.get(repo)?.eta(
+        runs_ahead(bors_for_repo, rollup_queue, position),
+        Timestamp::now(),
+    )
+}
+
+fn queued_info(
+    bors_for_pr: Option<&BorsPr>,
+    queue_status: QueueStatus,
+    merge_eta: Option<SignedDuration>,
+    approvers: Vec<Author>,
+) -> QueuedInfo {
     QueuedInfo {
-        // TODO: make this the bors approver
-        approvers: issue.assignees.iter().map(convert_author).collect(),
+        approvers,
         rollup_setting: bors_for_pr
             .map(|i| i.rollup_setting.clone())
             .unwrap_or_default(),
-        queue_status: rollup_status,
+        priority: bors_for_pr.map(|i| i.priority).unwrap_or(0),
+        mergeable: bors_for_pr.is_none_or(|i| i.mergeable),
+        queue_status,
+        merge_eta,
         url: bors_for_pr.map(|i| i.url.clone()),
     }
 }
 
+async fn sort_queued(
+    login_context: &LoginContext,
+    repo: &RepoInfo,
+    issue: &GitHubIssue,
+    bors_for_pr: Option<&BorsPr>,
+) -> QueuedInfo {
+    let Some(bors) = bors_for_pr else {
+        tracing::warn!("bors was none for {}#{}", repo.repo, issue.number);
+        return queued_info(None, QueueStatus::Unknown, None, issue.assignees.clone());
+    };
+
+    let bors_for_repo = login_context.state.bors_info(repo.clone()).await;
+    let rollup_queue = login_context
+        .state
+        .clone()
+        .rollup_info(repo.clone(), login_context.github.clone())
+        .await;
+
+    queued_info(
+        Some(bors),
+        queue_status(bors, issue.number, &rollup_queue),
+        merge_eta(login_context, repo, &bors_for_repo, &rollup_queue, bors).await,
+        bors_approvers(&issue.assignees, &bors.approver),
+    )
+}
+
+async fn find_try_build(
+    login_context: &LoginContext,
+    repo: &RepoInfo,
+    issue: &GitHubIssue,
+    bors_for_pr: Option<&BorsPr>,
+) -> Option<TryBuildStatus> {
+    // only bors knows a try build is what it's doing, looking through the comments of every PR
+    // waiting on bors to find out would cost a request each
+    if !bors_for_pr.is_some_and(|b| b.try_build) {
+        return None;
+    }
+
+    // bors posts a comment whenever the try build changes, which updates the PR
+    let try_build = login_context
+        .state
+        .try_builds
+        .get_or_reload(
+            &repo.repo,
+            issue.number,
+            issue.updated_at,
+            get_try_build(&login_context.github, &repo.repo, issue.number),
+        )
+        .await;
+
+    let try_build = match try_build {
+        Ok(i) => i?,
+        Err(e) => {
+            tracing::error!("error getting try build: {e}");
+            return None;
+        }
+    };
+
+    // but a running one can finish on its checks without bors saying so, which doesn't
+    if !try_build.is_done() {
+        login_context
+            .state
+            .try_builds
+            .remove(&repo.repo, issue.number)
+            .await;
+    }
+
+    Some(try_build)
+}
+
 async fn sort_status(
     login_context: &LoginContext,
     username: String,
     repo: &RepoInfo,
-    issue: &Issue,
-    pr: &PullRequest,
+    issue: &GitHubIssue,
+    pr: &GitHubPr,
     bors_for_repo: &Arc<BorsQueue>,
 ) -> PrStatus {
     let bors_for_pr = bors_for_repo.for_pr(issue.number);
 
-    let res = if pr.draft.is_some_and(|i| i) {
+    let res = if pr.draft {
         PrStatus::Draft {}
     } else if
-    // you're assigned for review
-    issue.assignees.iter().any(|i| i.login == username)
+    // you're assigned for review, or asked to review it
+    (issue.assignees.iter().any(|i| i.name == username)
+        || pr.requested_reviewers.iter().any(|i| i.name == username))
         // and it's waiting for review
         && label(issue, "S-waiting-on-review")
     {
@@ -150,122 +438,111 @@ async fn sort_status(
             other_reviewers: issue
                 .assignees
                 .iter()
-                .filter(|i| i.login != username)
-                .map(convert_author)
+                .chain(
+                    pr.requested_reviewers
+                        .iter()
+                        .filter(|i| !issue.assignees.contains(i)),
+                )
+                .filter(|i| i.name != username)
+                .cloned()
                 .collect(),
         }
     } else if
     // you're the creator of the PR
-    issue.user.login == username
+    issue.author.name == username
         // and it's waiting for the author
         && label(issue, "S-waiting-on-author")
     {
-        PrStatus::Ready {}
+        PrStatus::Ready { try_build: None }
+    } else if let Some(try_build) = find_try_build(login_context, repo, issue, bors_for_pr).await {
+        if try_build.is_done() && issue.author.name == username {
+            PrStatus::Ready {
+                try_build: Some(try_build),
+            }
+        } else {
+            PrStatus::Waiting {
+                wait_reason: WaitingReason::TryBuild(try_build),
+            }
+        }
     } else if
-    // if it's waiting for bors, it could be for:
-    // - a try build
-    // - it's in the queue
-    // TODO: try build detection
+    // if it's waiting for bors and not for a try build, it's in the queue
     label(issue, "S-waiting-on-bors")
-        || bors_for_pr
-            .is_some_and(|b| matches!(b.status, BorsStatus::Approved | BorsStatus::Pending))
+        || bors_for_pr.is_some_and(|b| {
+            !b.try_build && matches!(b.status, BorsStatus::Approved | BorsStatus::Pending)
+        })
     {
         PrStatus::Queued(sort_queued(login_context, repo, issue, bors_for_pr).await)
     } else {
         // the PR must be waiting for some reason. There are many reasons though...
         PrStatus::Waiting {
-            wait_reason: sort_waiting(login_context, issue, pr, bors_for_pr).await,
+            wait_reason: sort_waiting(login_context, repo, issue, pr, bors_for_pr).await,
         }
     };
 
     res
 }
 
-fn ci_status(issue: &Issue, pr: &PullRequest, bors_for_repo: &Arc<BorsQueue>) -> CiStatus {
-    let bors_for_pr = bors_for_repo.for_pr(issue.number);
+/// What the bors status says about CI, if it says anything
+fn bors_ci_status(status: &BorsStatus) -> Option<CiStatus> {
+    match status {
+        BorsStatus::Approved | BorsStatus::Success => Some(CiStatus::Good),
+        BorsStatus::Error | BorsStatus::Failure => Some(CiStatus::Bad),
+        BorsStatus::Pending => Some(CiStatus::Running),
+        BorsStatus::None | BorsStatus::Other(_) => None,
+    }
+}
 
-    match (pr.mergeable, &pr.mergeable_state, bors_for_pr) {
-        _ if pr.draft.is_some_and(|i| i) => CiStatus::Draft,
-        (Some(_), Some(MergeableState::Behind | MergeableState::Dirty), _) => CiStatus::Conflicted,
+/// Queued PRs get their CI status from bors, the others from the checks on their head commit
+async fn ci_status(
+    login_context: &LoginContext,
+    repo: &Repo,
+    pr: &GitHubPr,
+    bors_for_repo: &BorsQueue,
+) -> (CiStatus, Vec<Check>) {
+    // a try build says nothing about the CI of the PR itself
+    let bors_for_pr = bors_for_repo
+        .for_pr(pr.issue.number)
+        .filter(|b| !b.try_build);
 
-        (
-            _,
-            _,
-            Some(BorsPr {
-                status: BorsStatus::Approved,
-                ..
-            }),
-        ) => CiStatus::Good,
-        (
-            _,
-            _,
-            Some(BorsPr {
-                status: BorsStatus::Error,
-                ..
-            }),
-        ) => CiStatus::Bad,
-        (
-            _,
-            _,
-            Some(BorsPr {
-                status: BorsStatus::Failure,
-                ..
-            }),
-        ) => CiStatus::Bad,
-        (
-            _,
-            _,
-            Some(BorsPr {
-                status: BorsStatus::Pending,
-                ..
-            }),
-        ) => CiStatus::Running,
-        (
-            _,
-            _,
-            Some(BorsPr {
-                status: BorsStatus::Success,
-                ..
-            }),
-        ) => CiStatus::Good,
-        (
-            _,
-            _,
-            Some(BorsPr {
-                status: BorsStatus::None,
-                ..
-            }),
-        ) => CiStatus::Unknown,
-
-        // github: super unreliable
-        (None, _, _) => CiStatus::Running,
-        (Some(true), None, _) => CiStatus::Good,
-        (Some(_), Some(s), _) => match s {
-            MergeableState::Behind => CiStatus::Conflicted,
-            MergeableState::Dirty => CiStatus::Conflicted,
-            MergeableState::Blocked => CiStatus::Unknown,
-            MergeableState::Clean => CiStatus::Good,
-            MergeableState::Draft => CiStatus::Draft,
-            MergeableState::HasHooks => CiStatus::Good,
-            MergeableState::Unknown => CiStatus::Unknown,
-            MergeableState::Unstable => CiStatus::Good,
-            _ => todo!(),
-        },
-        _ => CiStatus::Unknown,
+    if pr.draft {
+        return (CiStatus::Draft, Vec::new());
+    }
+    if pr.conflicted {
+        return (CiStatus::Conflicted, Vec::new());
+    }
+    if let Some(status) = bors_for_pr.and_then(|b| bors_ci_status(&b.status)) {
+        return (status, Vec::new());
+    }
+
+    let checks = head_checks(
+        &login_context.github,
+        &login_context.state.checks,
+        repo,
+        pr.issue.number,
+        &pr.head_sha,
+    )
+    .await;
+
+    match checks {
+        Ok(checks) => (CiStatus::from_checks(&checks), checks),
+        Err(e) => {
+            tracing::error!("error getting checks: {e}");
+            (CiStatus::Unknown, Vec::new())
+        }
     }
 }
 
 #[derive(Clone, Debug)]
 pub enum PredeterminedCategory {
     Subscribed,
-    None(PullRequest),
+    None(Box<GitHubPr>),
 }
 
 pub async fn sort(
     login_context: &LoginContext,
     username: String,
     repo: &RepoInfo,
-    issue: &Issue,
+    issue: &GitHubIssue,
     predetermined_category: PredeterminedCategory,
 ) -> Option<Pr> {
     tracing::info!("sorting PR {}#{} {}", repo.repo, issue.number, issue.title);
@@ -278,26 +555,53 @@ pub async fn sort(
         return None;
     }
 
+    let (ci_status, checks) = match &predetermined_category {
+        PredeterminedCategory::None(pr) => {
+            ci_status(login_context, &repo.repo, pr, &bors_for_repo).await
+        }
+        PredeterminedCategory::Subscribed => (CiStatus::Unknown, Vec::new()),
+    };
+    let ci_failure = match (&predetermined_category, &ci_status) {
+        (PredeterminedCategory::None(pr), CiStatus::Bad) => {
+            ci_failure(login_context, repo, pr, &checks).await
+        }
+        _ => None,
+    };
+
+    let broken_rollup = match &predetermined_category {
+        PredeterminedCategory::None(_) => {
+            broken_rollup(login_context, &username, repo, issue).await
+        }
+        PredeterminedCategory::Subscribed => None,
+    };
+
     Some(Pr {
         repo: repo.repo.clone(),
         title: issue.title.clone(),
         description: issue.body.clone(),
         number: issue.number,
-        link: issue.html_url.clone(),
-        author: convert_author(&issue.user),
-        reviewers: issue.assignees.iter().map(convert_author).collect(),
+        link: issue.url.clone(),
+        author: issue.author.clone(),
+        reviewers: issue.assignees.clone(),
         status: match &predetermined_category {
             PredeterminedCategory::None(pr) => {
                 sort_status(login_context, username, repo, issue, pr, &bors_for_repo).await
             }
             PredeterminedCategory::Subscribed => PrStatus::Subscribed,
         },
-        ci_status: match &predetermined_category {
-            PredeterminedCategory::None(pr) => ci_status(issue, pr, &bors_for_repo),
-            PredeterminedCategory::Subscribed => CiStatus::Unknown,
+        ci_status,
+        checks,
+        ci_failure,
+        perf: match &predetermined_category {
+            PredeterminedCategory::None(_) => perf_summary(login_context, repo, issue).await,
+            PredeterminedCategory::Subscribed => None,
         },
+        broken_rollup,
+        stale_sources: Vec::new(),
+        scraped: true,
+        failed_at: None,
 
-        created: jiff::Timestamp::from_second(issue.created_at.timestamp()).unwrap(),
+        created: issue.created_at,
     })
 }
 
@@ -309,3 +613,141 @@ pub fn convert_author(author: &octocrab::models::Author) -> Author {
         profile_url: author.html_url.clone(),
     }
 }
+
+/// When bors failed to merge each of `prs`. Bors comments when it fails, which updates the PR,
+/// so what we found holds until the PR is updated again.
+async fn bors_failures(
+    login_context: &LoginContext,
+    repo: &Repo,
+    prs: &[&GitHubPr],
+) -> HashMap<u64, Timestamp> {
+    let cache = &login_context.state.bors_failures;
+    let mut res = HashMap::new();
+    let mut unknown = Vec::new();
+
+    for pr in prs {
+        match cache.get(repo, pr.issue.number, &pr.issue.updated_at).await {
+            Some(failed_at) => res.extend(failed_at.map(|i| (pr.issue.number, i))),
+            None => unknown.push(pr),
+        }
+    }
+
+    if unknown.is_empty() {
+        return res;
+    }
+
+    let numbers = unknown.iter().map(|i| i.issue.number).collect::<Vec<_>>();
+    let found = match failed_at(&login_context.github, repo, &numbers).await {
+        Ok(i) => i,
+        Err(e) => {
+            tracing::error!("error getting bors failures: {e}");
+            return res;
+        }
+    };
+
+    for pr in unknown {
+        let failed_at = found.get(&pr.issue.number).copied();
+        cache
+            .insert(repo, pr.issue.number, pr.issue.updated_at, failed_at)
+            .await;
+        res.extend(failed_at.map(|i| (pr.issue.number, i)));
+    }
+
+    res
+}
+
+/// Every PR bors is merging or failed to merge, for the page with the whole queue of a repo.
+/// The PRs are fetched in a few batches rather than one by one, and only CI failures we already
+/// know about are shown, getting the logs for the whole queue would take far too many requests.
+pub async fn sort_repo_queue(
+    login_context: Arc<LoginContext>,
+    repo: RepoInfo,
+) -> color_eyre::Result<Vec<Pr>> {
+    let state = &login_context.state;
+    let bors_for_repo = state.bors_info(repo.clone()).await;
+    let rollup_queue = state
+        .clone()
+        .rollup_info(repo.clone(), login_context.github.clone())
+        .await;
+
+    let entries = bors_for_repo
+        .items
+        .iter()
+        .filter(|b| !b.try_build && b.status != BorsStatus::None)
+        .collect::<Vec<_>>();
+
+    let pr_numbers = entries.iter().map(|i| i.pr_number).collect::<Vec<_>>();
+    let prs = get_prs(
+        &login_context.github,
+        &state.shared_prs,
+        &state.checks,
+        &repo.repo,
+        &pr_numbers,
+    )
+    .await
+    .context("get queued PRs")?;
+
+    let failed = entries
+        .iter()
+        .filter(|b| matches!(b.status, BorsStatus::Failure | BorsStatus::Error))
+        .filter_map(|b| prs.get(&b.pr_number))
+        .collect::<Vec<_>>();
+    let failed_at = bors_failures(&login_context, &repo.repo, &failed).await;
+
+    let mut res = Vec::new();
+    for bors in entries {
+        let Some(pr) = prs.get(&bors.pr_number) else {
+            tracing::error!("queued PR {}#{} not found", repo.repo, bors.pr_number);
+            continue;
+        };
+
+        let (ci_status, checks) = ci_status(&login_context, &repo.repo, pr, &bors_for_repo).await;
+        let ci_failure = match ci_status {
+            CiStatus::Bad => state
+                .ci_failures
+                .get(
+                    &repo.repo,
+                    pr.issue.number,
+                    &CiFailureVersion::new(pr, &checks),
+                )
+                .await
+                .flatten(),
+            _ => None,
+        };
+
+        let status = match bors.status {
+            // bors gave it back to the author
+            BorsStatus::Failure | BorsStatus::Error => PrStatus::Waiting {
+                wait_reason: WaitingReason::Author,
+            },
+            _ => PrStatus::Queued(queued_info(
+                Some(bors),
+                queue_status(bors, bors.pr_number, &rollup_queue),
+                merge_eta(&login_context, &repo, &bors_for_repo, &rollup_queue, bors).await,
+                bors_approvers(&pr.issue.assignees, &bors.approver),
+            )),
+        };
+
+        res.push(Pr {
+            repo: repo.repo.clone(),
+            title: pr.issue.title.clone(),
+            description: pr.issue.body.clone(),
+            number: bors.pr_number,
+            link: pr.issue.url.clone(),
+            author: pr.issue.author.clone(),
+            reviewers: pr.issue.assignees.clone(),
+            status,
+            ci_status,
+            checks,
+            perf: None,
+            ci_failure,
+            broken_rollup: None,
+            stale_sources: Vec::new(),
+            scraped: false,
+            failed_at: failed_at.get(&bors.pr_number).copied(),
+            created: pr.issue.created_at,
+        });
+    }
+
+    Ok(res)
+}